@@ -1,35 +1,72 @@
 mod modules;
 
+use modules::auto_backup::{auto_backup_loop, get_auto_backup_status, run_auto_backup_now};
 use modules::backup::{
-    export_backup, get_backup_collections_data, import_backup, read_backup_file, write_backup_file,
+    can_rollback_import, export_backup, export_backup_to_file, get_backup_collections_data,
+    import_backup, import_backup_selective, read_backup_file, rollback_last_import,
+    validate_backup, write_backup_file,
 };
+use modules::config_migration::{dismiss_config_errors, get_config_errors};
+use modules::config_watcher::config_watcher_loop;
+use modules::declarative_config::get_declarative_config;
+use modules::forecast::get_forecast_timeline;
+use modules::geolocation::get_resolved_location;
 use modules::greet::greet;
+use modules::location::{
+    delete_location_profile, save_location_profile, search_locations,
+    set_active_location_profile_cmd,
+};
+use modules::notifications::set_app_handle;
 use modules::scheduler::{
-    get_scheduler_status, initialize_scheduler, start_wallpaper_scheduler,
-    stop_wallpaper_scheduler, update_scheduler_collection_data,
+    get_scheduler_status, initialize_scheduler, resume_wallpaper_automation,
+    set_external_change_action, start_wallpaper_scheduler, stop_wallpaper_scheduler,
+    update_scheduler_collection_data,
 };
+use modules::secrets::init_secrets;
 use modules::settings::{get_app_settings, save_app_settings_cmd, test_weather_api};
+use modules::tray::{build_tray_menu, handle_location_menu_event};
+use modules::utils::{data_dir_override_args, init_data_dir_override, migrate_legacy_layout};
 use modules::wallpaper::{cleanup_unused_wallpapers, copy_wallpaper_image, set_wallpaper};
-use modules::weather::{clear_weather_cache, get_current_conditions, get_time_periods};
+use modules::wallpaper_backend::get_wallpaper_backends;
+use modules::weather::{
+    clear_weather_cache, get_current_conditions, get_time_periods, get_weather_cache_entries,
+};
 use tauri::{
-    menu::{Menu, MenuItem},
     tray::{MouseButton, TrayIconBuilder},
     Manager, WindowEvent,
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Must come first: every module resolves its paths through it
+    init_data_dir_override();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_autostart::init(
-            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
-            Some(vec![]),
-        ))
+        .plugin(tauri_plugin_notification::init())
+        .plugin(
+            // Relaunch with the same data directory override, if any
+            tauri_plugin_autostart::Builder::new()
+                .macos_launcher(tauri_plugin_autostart::MacosLauncher::LaunchAgent)
+                .args(data_dir_override_args())
+                .build(),
+        )
         .setup(|app| {
-            // Create tray menu
-            let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&quit_i])?;
+            // Let background tasks (scheduler, alerts) reach the app
+            set_app_handle(app.handle().clone());
+
+            // Settings and caches used to live in the data directory
+            migrate_legacy_layout();
+
+            // Load the API key from the secret store before anything reads settings
+            tauri::async_runtime::block_on(init_secrets());
+
+            // Scheduled backups check their own settings, so the loop always runs
+            tauri::async_runtime::spawn(auto_backup_loop());
+
+            // Pick up hand edits to settings, scheduler config and wallpapers
+            tauri::async_runtime::spawn(config_watcher_loop());
 
             // Create system tray icon WITHOUT attaching the menu directly
             // This prevents it from overriding the left-click behavior on some platforms
@@ -40,7 +77,11 @@ pub fn run() {
                     "quit" => {
                         app.exit(0);
                     }
-                    _ => {}
+                    id => {
+                        if !handle_location_menu_event(id) {
+                            eprintln!("Unknown tray menu item: {}", id);
+                        }
+                    }
                 })
                 .on_tray_icon_event(move |tray, event| {
                     match event {
@@ -97,7 +138,8 @@ pub fn run() {
                                     // IMPORTANT: Tauri v2 TrayIcon doesn't have a direct "popup_menu" method on the icon itself in the same way.
                                     // However, we can set the menu.
 
-                                    let _ = tray.set_menu(Some(menu.clone()));
+                                    // Rebuild the menu so location profiles are up to date
+                                    let _ = tray.set_menu(build_tray_menu(tray.app_handle()).ok());
                                     // On Windows, setting the menu usually makes it appear on the *next* interaction or immediately if triggered?
                                     // Actually, standard behavior is: if menu is set, right click shows it.
                                     // If left click is ALSO showing it, that's the issue.
@@ -122,7 +164,7 @@ pub fn run() {
                                     // On Right Click, we will set the menu.
                                     // On Left Click, we will UNSET the menu (to ensure it doesn't show) and toggle window.
 
-                                    let _ = tray.set_menu(Some(menu.clone()));
+                                    let _ = tray.set_menu(build_tray_menu(tray.app_handle()).ok());
                                 }
                                 _ => {}
                             }
@@ -144,21 +186,41 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             greet,
             set_wallpaper,
+            get_wallpaper_backends,
             copy_wallpaper_image,
             cleanup_unused_wallpapers,
             get_current_conditions,
             clear_weather_cache,
+            get_weather_cache_entries,
             get_time_periods,
+            get_forecast_timeline,
             start_wallpaper_scheduler,
             stop_wallpaper_scheduler,
             get_scheduler_status,
             initialize_scheduler,
             update_scheduler_collection_data,
+            resume_wallpaper_automation,
+            set_external_change_action,
             get_app_settings,
+            get_config_errors,
+            dismiss_config_errors,
+            get_declarative_config,
             save_app_settings_cmd,
             test_weather_api,
+            search_locations,
+            save_location_profile,
+            delete_location_profile,
+            set_active_location_profile_cmd,
+            get_resolved_location,
             export_backup,
+            export_backup_to_file,
             import_backup,
+            import_backup_selective,
+            validate_backup,
+            rollback_last_import,
+            can_rollback_import,
+            get_auto_backup_status,
+            run_auto_backup_now,
             write_backup_file,
             read_backup_file,
             get_backup_collections_data,
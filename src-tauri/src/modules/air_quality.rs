@@ -0,0 +1,56 @@
+use crate::modules::types::{AirQuality, PollenData};
+use crate::modules::settings::AppSettings;
+
+/// Highest count across all reported pollen types, if any were reported.
+pub fn max_pollen_count(pollen: &PollenData) -> Option<f32> {
+    [
+        pollen.hazel,
+        pollen.alder,
+        pollen.birch,
+        pollen.oak,
+        pollen.grass,
+        pollen.mugwort,
+        pollen.ragweed,
+    ]
+    .iter()
+    .flatten()
+    .copied()
+    .fold(None, |max: Option<f32>, count| Some(max.map_or(count, |m| m.max(count))))
+}
+
+pub fn is_smog(air_quality: &AirQuality, settings: &AppSettings) -> bool {
+    let index_exceeded = air_quality
+        .us_epa_index
+        .map(|index| index >= settings.aqi_smog_threshold)
+        .unwrap_or(false);
+    let pm25_exceeded = air_quality
+        .pm2_5
+        .map(|pm25| pm25 >= settings.pm25_smog_threshold)
+        .unwrap_or(false);
+
+    index_exceeded || pm25_exceeded
+}
+
+/// Maps air quality and pollen readings to "smog" / "high_pollen" categories using
+/// the thresholds from settings.
+pub fn get_air_quality_categories(
+    air_quality: Option<&AirQuality>,
+    pollen: Option<&PollenData>,
+    settings: &AppSettings,
+) -> Vec<String> {
+    let mut categories = Vec::new();
+
+    if air_quality.map(|aq| is_smog(aq, settings)).unwrap_or(false) {
+        categories.push("smog".to_string());
+    }
+
+    if pollen
+        .and_then(max_pollen_count)
+        .map(|count| count >= settings.pollen_high_threshold)
+        .unwrap_or(false)
+    {
+        categories.push("high_pollen".to_string());
+    }
+
+    categories
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use chrono::DateTime as ChronoDateTime;
+use crate::modules::types::{WeatherAlert, WeatherApiResponse};
+use crate::modules::settings::load_app_settings;
+use crate::modules::notifications::send_notification;
+
+// Alerts seen on the previous check (alert_id() -> title), so we only notify on start/end
+static KNOWN_ALERTS: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+fn parse_alert_time(value: &str) -> Option<i64> {
+    ChronoDateTime::parse_from_rfc3339(value).ok().map(|dt| dt.timestamp())
+}
+
+fn alert_id(alert: &WeatherAlert) -> String {
+    format!("{}|{}|{}", alert.event, alert.headline, alert.effective)
+}
+
+fn alert_title(alert: &WeatherAlert) -> String {
+    if !alert.event.is_empty() {
+        alert.event.clone()
+    } else if !alert.headline.is_empty() {
+        alert.headline.clone()
+    } else {
+        "Weather alert".to_string()
+    }
+}
+
+/// Alerts whose effective/expires window covers the given UTC timestamp.
+/// Missing or unparsable bounds are treated as open-ended.
+pub fn active_alerts_at(weather_data: &WeatherApiResponse, timestamp: i64) -> Vec<WeatherAlert> {
+    let alerts = match weather_data.alerts.as_ref() {
+        Some(alerts) => &alerts.alert,
+        None => return Vec::new(),
+    };
+
+    alerts
+        .iter()
+        .filter(|alert| {
+            let started = parse_alert_time(&alert.effective).map(|t| t <= timestamp).unwrap_or(true);
+            let not_expired = parse_alert_time(&alert.expires).map(|t| timestamp < t).unwrap_or(true);
+            started && not_expired
+        })
+        .cloned()
+        .collect()
+}
+
+fn severity_rank(severity: &str) -> i32 {
+    match severity.to_lowercase().as_str() {
+        "extreme" => 4,
+        "severe" => 3,
+        "moderate" => 2,
+        "minor" => 1,
+        _ => 0,
+    }
+}
+
+/// Maps alerts in effect to categories: the most severe "alert_<severity>" sub-category
+/// followed by the generic "alert" category, so a single alert wallpaper covers all levels.
+pub fn get_alert_categories(alerts: &[WeatherAlert]) -> Vec<String> {
+    let most_severe = match alerts.iter().max_by_key(|alert| severity_rank(&alert.severity)) {
+        Some(alert) => alert,
+        None => return Vec::new(),
+    };
+
+    let mut categories = Vec::new();
+    match severity_rank(&most_severe.severity) {
+        4 => categories.push("alert_extreme".to_string()),
+        3 => categories.push("alert_severe".to_string()),
+        2 => categories.push("alert_moderate".to_string()),
+        1 => categories.push("alert_minor".to_string()),
+        _ => {}
+    }
+    categories.push("alert".to_string());
+    categories
+}
+
+/// Compares the alerts in effect against the previous check and raises a desktop
+/// notification for each alert that started or ended, if enabled in settings.
+pub fn notify_alert_transitions(alerts: &[WeatherAlert]) {
+    let current: HashMap<String, String> = alerts
+        .iter()
+        .map(|alert| (alert_id(alert), alert_title(alert)))
+        .collect();
+
+    let previous = {
+        let mut known_alerts = KNOWN_ALERTS.lock().unwrap();
+        known_alerts.replace(current.clone())
+    };
+
+    // First check after startup only establishes the baseline
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return,
+    };
+
+    if !load_app_settings().alert_notifications_enabled {
+        return;
+    }
+
+    for alert in alerts {
+        if !previous.contains_key(&alert_id(alert)) {
+            let body = if alert.headline.is_empty() { alert.areas.clone() } else { alert.headline.clone() };
+            send_notification(&format!("Weather alert: {}", alert_title(alert)), &body);
+        }
+    }
+
+    for (id, title) in &previous {
+        if !current.contains_key(id) {
+            send_notification("Weather alert ended", &format!("{} is no longer in effect", title));
+        }
+    }
+}
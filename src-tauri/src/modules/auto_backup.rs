@@ -0,0 +1,193 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::time::interval;
+use crate::modules::backup::create_backup_file;
+use crate::modules::persistence::lock_file;
+use crate::modules::scheduler::get_collection_data;
+use crate::modules::settings::{load_app_settings, AppSettings};
+use crate::modules::utils::get_app_data_dir;
+
+const BACKUP_FILE_PREFIX: &str = "wallpaperthing-auto-";
+const CHECK_INTERVAL_MINUTES: u64 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AutoBackupStatus {
+    pub last_success_at: Option<String>, // RFC 3339
+    pub last_backup_path: Option<String>,
+    pub last_attempt_at: Option<String>,
+    pub last_error: Option<String>, // Cleared by the next successful backup
+    #[serde(default)]
+    pub next_due_at: Option<String>, // Only filled in when reported, never stored
+}
+
+fn get_status_path() -> Result<PathBuf, String> {
+    let app_dir = get_app_data_dir()?;
+    Ok(app_dir.join("auto_backup_status.json"))
+}
+
+pub fn load_status() -> AutoBackupStatus {
+    get_status_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// Applies `update` to the stored status and writes it back, all under the status file's
+// lock, so a manual backup finishing alongside a scheduled one doesn't lose its outcome
+fn update_status(update: impl FnOnce(&mut AutoBackupStatus)) -> Result<AutoBackupStatus, String> {
+    let status_path = get_status_path()?;
+    let status_lock = lock_file(&status_path)?;
+    let mut status = load_status();
+    update(&mut status);
+
+    status_lock.write_json(&status)
+        .map_err(|e| format!("Failed to write backup status: {}", e))?;
+    Ok(status)
+}
+
+fn get_backup_directory(settings: &AppSettings) -> Result<PathBuf, String> {
+    match settings.auto_backup_directory.as_deref().filter(|dir| !dir.trim().is_empty()) {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => Ok(get_app_data_dir()?.join("backups")),
+    }
+}
+
+fn backup_period(settings: &AppSettings) -> chrono::Duration {
+    match settings.auto_backup_frequency.as_str() {
+        "weekly" => chrono::Duration::weeks(1),
+        _ => chrono::Duration::days(1),
+    }
+}
+
+fn next_due_at(settings: &AppSettings, status: &AutoBackupStatus) -> Option<DateTime<Utc>> {
+    let last_success = status
+        .last_success_at
+        .as_deref()
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())?;
+    Some(last_success.with_timezone(&Utc) + backup_period(settings))
+}
+
+fn is_backup_due(settings: &AppSettings, status: &AutoBackupStatus) -> bool {
+    next_due_at(settings, status)
+        .map(|due| Utc::now() >= due)
+        .unwrap_or(true)
+}
+
+// Deletes the oldest automatic backups beyond the retention count. Only files this
+// module created are considered; timestamped names sort chronologically.
+fn prune_old_backups(directory: &Path, retention: u32) -> Result<(), String> {
+    let entries = fs::read_dir(directory)
+        .map_err(|e| format!("Failed to read backup directory: {}", e))?;
+
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with(BACKUP_FILE_PREFIX) && name.ends_with(".zip"))
+                .unwrap_or(false)
+        })
+        .collect();
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(retention.max(1) as usize);
+    for path in backups.into_iter().take(excess) {
+        if let Err(e) = fs::remove_file(&path) {
+            eprintln!("Warning: Failed to remove old backup {}: {}", path.display(), e);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_backup(settings: &AppSettings) -> Result<PathBuf, String> {
+    // Without collections data the backup couldn't be restored properly, so wait for the frontend to sync it
+    let collections_data = get_collection_data()
+        .ok_or("Collections have not been loaded yet")?
+        .to_string();
+
+    let directory = get_backup_directory(settings)?;
+    fs::create_dir_all(&directory)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let filename = format!("{}{}.zip", BACKUP_FILE_PREFIX, Utc::now().format("%Y%m%d-%H%M%S"));
+    let destination = directory.join(filename);
+    let include_secrets = settings.auto_backup_include_secrets;
+    let retention = settings.auto_backup_retention;
+
+    tokio::task::spawn_blocking(move || {
+        create_backup_file(&destination, Some(collections_data), include_secrets)?;
+        prune_old_backups(&directory, retention)?;
+        Ok(destination)
+    })
+    .await
+    .map_err(|e| format!("Backup task failed: {}", e))?
+}
+
+// Runs a backup and records the outcome in the status file
+async fn run_and_record(settings: &AppSettings) -> Result<AutoBackupStatus, String> {
+    let attempted_at = Utc::now().to_rfc3339();
+
+    let result = run_backup(settings).await;
+    let status = update_status(|status| {
+        status.last_attempt_at = Some(attempted_at.clone());
+        match &result {
+            Ok(path) => {
+                println!("Automatic backup written to {}", path.display());
+                status.last_success_at = Some(attempted_at);
+                status.last_backup_path = Some(path.to_string_lossy().to_string());
+                status.last_error = None;
+            }
+            Err(e) => {
+                eprintln!("Automatic backup failed: {}", e);
+                status.last_error = Some(e.clone());
+            }
+        }
+    })?;
+    result.map(|_| status)
+}
+
+/// Background loop that creates a backup whenever one is due. Settings are re-read on
+/// every check, so enabling or changing the schedule takes effect without a restart.
+pub async fn auto_backup_loop() {
+    let mut interval_timer = interval(Duration::from_secs(CHECK_INTERVAL_MINUTES * 60));
+
+    loop {
+        interval_timer.tick().await;
+
+        let settings = load_app_settings();
+        if !settings.auto_backup_enabled || !is_backup_due(&settings, &load_status()) {
+            continue;
+        }
+
+        // Right after startup the frontend may not have synced collections yet; try again next check
+        if get_collection_data().is_none() {
+            continue;
+        }
+
+        let _ = run_and_record(&settings).await;
+    }
+}
+
+#[tauri::command]
+pub async fn get_auto_backup_status() -> Result<AutoBackupStatus, String> {
+    let settings = load_app_settings();
+    let mut status = load_status();
+
+    if settings.auto_backup_enabled {
+        status.next_due_at = Some(next_due_at(&settings, &status).unwrap_or_else(Utc::now).to_rfc3339());
+    }
+
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn run_auto_backup_now() -> Result<AutoBackupStatus, String> {
+    let settings = load_app_settings();
+    run_and_record(&settings).await
+}
@@ -1,9 +1,29 @@
+use std::collections::HashMap;
 use std::fs;
-use std::io::{Write, Read, Cursor};
+use std::io::{BufWriter, Write, Read, Cursor, Seek};
+use std::path::{Path, PathBuf};
 use serde::{Serialize, Deserialize};
-use crate::modules::settings::{load_app_settings, save_app_settings, AppSettings};
-use crate::modules::utils::get_app_data_dir;
-use zip::{ZipWriter, ZipArchive, write::FileOptions};
+use tauri::{AppHandle, Emitter};
+use crate::modules::settings::{load_app_settings, AppSettings};
+use crate::modules::utils::{get_app_config_dir, get_app_data_dir};
+use crate::modules::backup_restore::{
+    commit_staged_import, create_staging_dir, has_rollback_snapshot, remove_staging_dir,
+    rollback_to_snapshot, stage_existing_wallpapers, staged_wallpapers_dir, SCHEDULER_CONFIG_FILE,
+    SETTINGS_FILE,
+};
+use crate::modules::backup_integrity::{
+    check_archive_limits, checksum_bytes, copy_limited, copy_with_checksum, is_supported_version,
+    read_archive_entry, sanitize_relative_path, validate_archive, BackupManifest,
+    BackupValidationReport, ManifestEntry, MANIFEST_FILE, MAX_ENTRY_SIZE, MAX_TOTAL_SIZE,
+    METADATA_FILE,
+};
+use crate::modules::secrets::{cached_api_key, migrate_plaintext_api_key, seal_api_key, store_api_key, unseal_api_key};
+use crate::modules::backup_crypto::{decrypt_secrets, encrypt_secrets, BackupSecrets, EncryptedSecrets};
+use crate::modules::backup_merge::{
+    plan_collection_renames, rename_wallpaper_path, restored_wallpaper_name, select_collections,
+    ImportMode, ImportOptions, ImportResult,
+};
+use zip::{ZipWriter, ZipArchive, CompressionMethod, write::FileOptions};
 use serde_json::Value;
 
 #[derive(Serialize, Deserialize)]
@@ -14,171 +34,644 @@ struct BackupMetadata {
     wallpaper_files: Vec<String>, // List of wallpaper filenames in the ZIP
     version: String,
     created_at: String,
+    #[serde(default = "default_true")]
+    secrets_included: bool, // False when exported without the API key
+    encrypted_secrets: Option<EncryptedSecrets>, // Set when secrets were exported with a passphrase
 }
 
-#[tauri::command]
-pub async fn export_backup(collections_data: Option<String>) -> Result<Vec<u8>, String> {
-    let app_settings = load_app_settings();
-    let app_dir = get_app_data_dir()?;
-    let wallpapers_dir = app_dir.join("wallpapers");
-    
-    // Create ZIP in memory
-    let mut zip_buffer = Vec::new();
-    {
-        let cursor = Cursor::new(&mut zip_buffer);
-        let mut zip = ZipWriter::new(cursor);
-        let options = FileOptions::default()
-            .compression_method(zip::CompressionMethod::Stored)
-            .unix_permissions(0o755);
-        
-        // Collect wallpaper filenames
-        let mut wallpaper_files = Vec::new();
-        
-        if wallpapers_dir.exists() {
-            let entries = fs::read_dir(&wallpapers_dir)
-                .map_err(|e| format!("Failed to read wallpapers directory: {}", e))?;
-                
-            for entry in entries {
-                let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-                let path = entry.path();
-                
-                if path.is_file() {
-                    if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                        // Add image to ZIP
-                        let zip_path = format!("wallpapers/{}", filename);
-                        zip.start_file(&zip_path, options)
-                            .map_err(|e| format!("Failed to start ZIP file: {}", e))?;
-                        
-                        let file_data = fs::read(&path)
-                            .map_err(|e| format!("Failed to read wallpaper {}: {}", filename, e))?;
-                        
-                        zip.write_all(&file_data)
-                            .map_err(|e| format!("Failed to write to ZIP: {}", e))?;
-                        
-                        wallpaper_files.push(filename.to_string());
-                    }
-                }
-            }
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Clone)]
+pub struct BackupProgress {
+    pub processed_files: usize,
+    pub total_files: usize,
+    pub processed_bytes: u64,
+    pub total_bytes: u64,
+    pub current_file: Option<String>,
+}
+
+struct ExportOptions {
+    compression: CompressionMethod,
+    collection_id: Option<String>, // Only export this collection's wallpapers and data
+    include_secrets: bool,
+    passphrase: Option<String>, // Encrypts the secrets instead of storing them in plaintext
+}
+
+fn parse_compression(compression: Option<&str>) -> Result<CompressionMethod, String> {
+    match compression.unwrap_or("deflate") {
+        "stored" => Ok(CompressionMethod::Stored),
+        "deflate" => Ok(CompressionMethod::Deflated),
+        "zstd" => Ok(CompressionMethod::Zstd),
+        other => Err(format!("Unsupported compression method: {}", other)),
+    }
+}
+
+// Wallpaper files to include, as (path on disk, name relative to the wallpapers directory).
+// Relative names always use "/" so archives are portable between platforms.
+fn collect_wallpaper_files(wallpapers_dir: &Path, collection_id: Option<&str>) -> Result<Vec<(PathBuf, String)>, String> {
+    let (source_dir, prefix) = match collection_id {
+        Some(id) => (wallpapers_dir.join(id), format!("{}/", id)),
+        None => (wallpapers_dir.to_path_buf(), String::new()),
+    };
+
+    let mut files = Vec::new();
+    if source_dir.exists() {
+        walk_wallpaper_dir(&source_dir, &prefix, &mut files)?;
+    }
+
+    Ok(files)
+}
+
+fn walk_wallpaper_dir(dir: &Path, prefix: &str, files: &mut Vec<(PathBuf, String)>) -> Result<(), String> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read wallpapers directory: {}", e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
+
+        let name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+
+        if path.is_dir() {
+            walk_wallpaper_dir(&path, &format!("{}{}/", prefix, name), files)?;
+        } else if path.is_file() {
+            files.push((path, format!("{}{}", prefix, name)));
         }
-        
-        // Load scheduler config if it exists
-        let scheduler_config = {
-            let scheduler_config_path = app_dir.join("scheduler_config.json");
-            if scheduler_config_path.exists() {
-                fs::read_to_string(scheduler_config_path)
-                    .ok()
-                    .and_then(|content| serde_json::from_str::<Value>(&content).ok())
-            } else {
-                None
-            }
+    }
+
+    Ok(())
+}
+
+// Location on disk for a "/"-separated name relative to the wallpapers directory.
+// Names come from untrusted backups, so anything that could escape the directory is rejected.
+fn wallpaper_path_for(wallpapers_dir: &Path, relative_name: &str) -> Result<PathBuf, String> {
+    let components = sanitize_relative_path(relative_name)?;
+    Ok(components
+        .into_iter()
+        .fold(wallpapers_dir.to_path_buf(), |path, component| path.join(component)))
+}
+
+// Maps an imagePath from the machine that created the backup to the backed-up wallpaper it refers to.
+// Matches on the path below "wallpapers/" first, then on the bare filename for older flat backups.
+fn backed_up_wallpaper_name<'a>(image_path: &str, wallpaper_files: &'a [String]) -> Option<&'a str> {
+    let normalized = image_path.replace('\\', "/");
+
+    if let Some(index) = normalized.rfind("/wallpapers/") {
+        let relative = &normalized[index + "/wallpapers/".len()..];
+        if let Some(file) = wallpaper_files.iter().find(|file| *file == relative) {
+            return Some(file);
+        }
+    }
+
+    let filename = normalized.rsplit('/').next()?;
+    let mut matches = wallpaper_files
+        .iter()
+        .filter(|file| file.rsplit('/').next() == Some(filename));
+    match (matches.next(), matches.next()) {
+        (Some(file), None) => Some(file),
+        _ => None,
+    }
+}
+
+/// Rewrites every category's imagePath in the collections data so it points at the
+/// wallpaper restored into this machine's wallpapers directory, following any
+/// collection renames made while merging.
+fn rewrite_image_paths(
+    collections_data: &mut Value,
+    wallpaper_files: &[String],
+    wallpapers_dir: &Path,
+    renames: &HashMap<String, String>,
+) {
+    let collections = match collections_data.get_mut("collections").and_then(|c| c.as_object_mut()) {
+        Some(collections) => collections,
+        None => return,
+    };
+
+    for collection in collections.values_mut() {
+        let settings = match collection.get_mut("settings").and_then(|s| s.as_object_mut()) {
+            Some(settings) => settings,
+            None => continue,
         };
 
-        // Parse collections data if provided
-        let parsed_collections_data = collections_data
-            .and_then(|data| serde_json::from_str::<Value>(&data).ok());
-
-        // Create metadata
-        let metadata = BackupMetadata {
-            settings: app_settings,
-            scheduler_config,
-            collections_data: parsed_collections_data,
-            wallpaper_files,
-            version: "2.1".to_string(), // Updated version for complete backup
-            created_at: chrono::Utc::now().to_rfc3339(),
+        for category_setting in settings.values_mut() {
+            let restored = category_setting
+                .get("imagePath")
+                .and_then(|v| v.as_str())
+                .and_then(|image_path| backed_up_wallpaper_name(image_path, wallpaper_files))
+                .and_then(|file| wallpaper_path_for(wallpapers_dir, &rename_wallpaper_path(file, renames)).ok());
+
+            if let Some(restored) = restored {
+                category_setting["imagePath"] = Value::String(restored.to_string_lossy().to_string());
+            }
+        }
+    }
+}
+
+// Keeps only the requested collection in the frontend collections data
+fn filter_collections_data(collections_data: Value, collection_id: &str) -> Value {
+    let mut filtered = collections_data;
+    if let Some(collections) = filtered.get_mut("collections").and_then(|c| c.as_object_mut()) {
+        collections.retain(|id, _| id == collection_id);
+    }
+    if let Some(data) = filtered.as_object_mut() {
+        data.insert("activeCollectionId".to_string(), Value::String(collection_id.to_string()));
+    }
+    filtered
+}
+
+/// Writes a complete backup archive into `writer`, streaming each wallpaper from disk
+/// and reporting progress after every file.
+fn write_backup_archive<W, F>(
+    writer: W,
+    collections_data: Option<String>,
+    options: &ExportOptions,
+    mut on_progress: F,
+) -> Result<W, String>
+where
+    W: Write + Seek,
+    F: FnMut(&BackupProgress),
+{
+    let mut app_settings = load_app_settings();
+    let app_dir = get_app_data_dir()?;
+    let wallpapers_dir = app_dir.join("wallpapers");
+
+    // Secrets are left in the settings, moved into an encrypted section, or dropped
+    let encrypted_secrets = if !options.include_secrets {
+        app_settings.weather_api_key.clear();
+        None
+    } else if let Some(passphrase) = options.passphrase.as_deref() {
+        let secrets = BackupSecrets {
+            weather_api_key: std::mem::take(&mut app_settings.weather_api_key),
         };
-        
-        // Add metadata JSON to ZIP
-        let metadata_json = serde_json::to_string_pretty(&metadata)
-            .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
-        
-        zip.start_file("backup.json", options)
-            .map_err(|e| format!("Failed to start metadata file: {}", e))?;
-        
-        zip.write_all(metadata_json.as_bytes())
-            .map_err(|e| format!("Failed to write metadata: {}", e))?;
-        
-        zip.finish()
-            .map_err(|e| format!("Failed to finish ZIP: {}", e))?;
+        Some(encrypt_secrets(&secrets, passphrase)?)
+    } else {
+        None
+    };
+
+    let mut zip = ZipWriter::new(writer);
+    let file_options = FileOptions::default()
+        .compression_method(options.compression)
+        .unix_permissions(0o755);
+
+    let files = collect_wallpaper_files(&wallpapers_dir, options.collection_id.as_deref())?;
+    let total_bytes = files
+        .iter()
+        .map(|(path, _)| fs::metadata(path).map(|m| m.len()).unwrap_or(0))
+        .sum();
+
+    let mut progress = BackupProgress {
+        processed_files: 0,
+        total_files: files.len(),
+        processed_bytes: 0,
+        total_bytes,
+        current_file: None,
+    };
+    on_progress(&progress);
+
+    // Collect wallpaper filenames and their checksums for the manifest
+    let mut wallpaper_files = Vec::new();
+    let mut manifest = BackupManifest::default();
+
+    for (path, relative_name) in &files {
+        // Add image to ZIP
+        let zip_path = format!("wallpapers/{}", relative_name);
+        zip.start_file(&zip_path, file_options)
+            .map_err(|e| format!("Failed to start ZIP file: {}", e))?;
+
+        let mut source = fs::File::open(path)
+            .map_err(|e| format!("Failed to read wallpaper {}: {}", relative_name, e))?;
+
+        let (copied, sha256) = copy_with_checksum(&mut source, &mut zip)
+            .map_err(|e| format!("Failed to write to ZIP: {}", e))?;
+
+        wallpaper_files.push(relative_name.clone());
+        manifest.files.push(ManifestEntry {
+            path: zip_path,
+            size: copied,
+            sha256,
+        });
+
+        progress.processed_files += 1;
+        progress.processed_bytes += copied;
+        progress.current_file = Some(relative_name.clone());
+        on_progress(&progress);
+    }
+
+    // Load scheduler config if it exists
+    let scheduler_config = {
+        let scheduler_config_path = get_app_config_dir()?.join(SCHEDULER_CONFIG_FILE);
+        if scheduler_config_path.exists() {
+            fs::read_to_string(scheduler_config_path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        } else {
+            None
+        }
+    };
+
+    // Parse collections data if provided
+    let parsed_collections_data = collections_data
+        .and_then(|data| serde_json::from_str::<Value>(&data).ok())
+        .map(|data| match options.collection_id.as_deref() {
+            Some(id) => filter_collections_data(data, id),
+            None => data,
+        });
+
+    // Create metadata
+    let metadata = BackupMetadata {
+        settings: app_settings,
+        scheduler_config,
+        collections_data: parsed_collections_data,
+        wallpaper_files,
+        version: "2.4".to_string(), // Adds optional passphrase-encrypted secrets
+        created_at: chrono::Utc::now().to_rfc3339(),
+        secrets_included: options.include_secrets,
+        encrypted_secrets,
+    };
+
+    // Add metadata JSON to ZIP
+    let metadata_json = serde_json::to_string_pretty(&metadata)
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+
+    zip.start_file(METADATA_FILE, file_options)
+        .map_err(|e| format!("Failed to start metadata file: {}", e))?;
+
+    zip.write_all(metadata_json.as_bytes())
+        .map_err(|e| format!("Failed to write metadata: {}", e))?;
+
+    manifest.files.push(ManifestEntry {
+        path: METADATA_FILE.to_string(),
+        size: metadata_json.len() as u64,
+        sha256: checksum_bytes(metadata_json.as_bytes()),
+    });
+
+    // Manifest goes last so it covers every other entry
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+
+    zip.start_file(MANIFEST_FILE, file_options)
+        .map_err(|e| format!("Failed to start manifest file: {}", e))?;
+
+    zip.write_all(manifest_json.as_bytes())
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish ZIP: {}", e))
+}
+
+#[tauri::command]
+pub async fn export_backup(collections_data: Option<String>) -> Result<Vec<u8>, String> {
+    // Create ZIP in memory
+    // Without a passphrase the API key would be in plaintext, so it's left out
+    let options = ExportOptions {
+        compression: CompressionMethod::Stored,
+        collection_id: None,
+        include_secrets: false,
+        passphrase: None,
+    };
+    let cursor = write_backup_archive(Cursor::new(Vec::new()), collections_data, &options, |_| {})?;
+
+    Ok(cursor.into_inner())
+}
+
+/// Streams a backup straight to `path` without passing the archive through IPC.
+/// Emits `backup-export-progress` events while wallpapers are written.
+#[tauri::command]
+pub async fn export_backup_to_file(
+    app: AppHandle,
+    path: String,
+    collections_data: Option<String>,
+    compression: Option<String>,
+    collection_id: Option<String>,
+    include_secrets: Option<bool>,
+    passphrase: Option<String>,
+) -> Result<String, String> {
+    // Manual backups end up in all sorts of places, so the API key only goes in encrypted
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    let include_secrets = include_secrets.unwrap_or(false);
+    if include_secrets && passphrase.is_none() {
+        return Err("A passphrase is required to include the API key in a backup".to_string());
     }
+    let options = ExportOptions {
+        compression: parse_compression(compression.as_deref())?,
+        collection_id,
+        include_secrets,
+        passphrase,
+    };
+
+    let destination = PathBuf::from(&path);
+    let result = tokio::task::spawn_blocking(move || {
+        stream_backup_to_file(&destination, collections_data, &options, |progress| {
+            let _ = app.emit("backup-export-progress", progress.clone());
+        })
+    })
+    .await
+    .map_err(|e| format!("Backup export task failed: {}", e))?;
+
+    result?;
+    Ok(format!("Backup saved to: {}", path))
+}
+
+// Writes next to the destination first so a failed export never leaves a truncated backup behind
+fn stream_backup_to_file<F>(
+    destination: &Path,
+    collections_data: Option<String>,
+    options: &ExportOptions,
+    on_progress: F,
+) -> Result<(), String>
+where
+    F: FnMut(&BackupProgress),
+{
+    let partial_path = destination.with_extension("zip.partial");
+    let file = fs::File::create(&partial_path)
+        .map_err(|e| format!("Failed to create backup file: {}", e))?;
+
+    let written = write_backup_archive(BufWriter::new(file), collections_data, options, on_progress)
+        .and_then(|writer| writer.into_inner().map_err(|e| format!("Failed to flush backup file: {}", e)))
+        .and_then(|file| file.sync_all().map_err(|e| format!("Failed to flush backup file: {}", e)));
+
+    if let Err(e) = written {
+        let _ = fs::remove_file(&partial_path);
+        return Err(e);
+    }
+
+    fs::rename(&partial_path, destination)
+        .map_err(|e| format!("Failed to move backup into place: {}", e))
+}
+
+/// Writes a full backup to `destination` without involving the frontend. Used by
+/// scheduled backups, which never have a passphrase, so secrets are either stored
+/// in plaintext or left out.
+pub fn create_backup_file(destination: &Path, collections_data: Option<String>, include_secrets: bool) -> Result<(), String> {
+    let options = ExportOptions {
+        compression: CompressionMethod::Deflated,
+        collection_id: None,
+        include_secrets,
+        passphrase: None,
+    };
+    stream_backup_to_file(destination, collections_data, &options, |_| {})
+}
+
+// Reads backup.json with a size cap, so a crafted archive can't exhaust memory
+fn read_backup_metadata<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<BackupMetadata, String> {
+    let metadata_content = read_archive_entry(archive, METADATA_FILE)
+        .map_err(|e| format!("Backup metadata not found: {}", e))?;
     
-    Ok(zip_buffer)
+    serde_json::from_str(&metadata_content)
+        .map_err(|e| format!("Invalid metadata format: {}", e))
+}
+
+#[tauri::command]
+pub async fn import_backup(zip_data: Vec<u8>, force: Option<bool>, passphrase: Option<String>) -> Result<String, String> {
+    let options = ImportOptions {
+        passphrase,
+        ..ImportOptions::default()
+    };
+    let result = restore_backup(zip_data, force.unwrap_or(false), &options)?;
+    secure_restored_api_key().await;
+    Ok(result.message)
 }
 
+/// Restores only the selected parts of a backup, optionally merging its collections with
+/// the existing ones. Collections live in the frontend store, so they're returned for it
+/// to apply rather than written here.
 #[tauri::command]
-pub async fn import_backup(zip_data: Vec<u8>) -> Result<String, String> {
+pub async fn import_backup_selective(
+    zip_data: Vec<u8>,
+    force: Option<bool>,
+    options: ImportOptions,
+) -> Result<ImportResult, String> {
+    let result = restore_backup(zip_data, force.unwrap_or(false), &options)?;
+    secure_restored_api_key().await;
+    Ok(result)
+}
+
+// Restored settings files carry the API key in plaintext; move it to the secret store
+async fn secure_restored_api_key() {
+    if let Err(e) = migrate_plaintext_api_key().await {
+        eprintln!("Warning: Failed to move restored API key to secret store: {}", e);
+    }
+}
+
+fn restore_backup(zip_data: Vec<u8>, force: bool, options: &ImportOptions) -> Result<ImportResult, String> {
     let cursor = Cursor::new(zip_data);
     let mut archive = ZipArchive::new(cursor)
         .map_err(|e| format!("Invalid ZIP file: {}", e))?;
     
-    // Read metadata first
-    let mut metadata_file = archive.by_name("backup.json")
-        .map_err(|e| format!("Backup metadata not found: {}", e))?;
+    // Size limits and unsafe paths are never overridable, even with force
+    check_archive_limits(&mut archive)?;
     
-    let mut metadata_content = String::new();
-    metadata_file.read_to_string(&mut metadata_content)
-        .map_err(|e| format!("Failed to read metadata: {}", e))?;
+    // Refuse damaged backups unless the user explicitly chose to import anyway
+    let report = validate_archive(&mut archive);
+    if !report.is_valid && !force {
+        return Err(format!("Backup failed integrity checks: {}", report.summary()));
+    }
     
-    let metadata: BackupMetadata = serde_json::from_str(&metadata_content)
-        .map_err(|e| format!("Invalid metadata format: {}", e))?;
+    // Read metadata first
+    let mut metadata = read_backup_metadata(&mut archive)?;
     
     // Version compatibility check
-    if !metadata.version.starts_with("2.") {
+    if !is_supported_version(&metadata.version) {
         return Err(format!("Unsupported backup version: {}. Please use a newer backup.", metadata.version));
     }
     
-    drop(metadata_file); // Release the borrow
+    for filename in &metadata.wallpaper_files {
+        sanitize_relative_path(filename)?;
+    }
+    
+    // Decrypt before anything is staged so a wrong passphrase changes nothing
+    if options.settings {
+        metadata.settings.weather_api_key = restored_api_key(&metadata, options)?;
+    }
     
     let app_dir = get_app_data_dir()?;
-    let wallpapers_dir = app_dir.join("wallpapers");
+    let renames = plan_collection_renames(metadata.collections_data.as_ref(), options);
+    
+    // Extract everything into a staging directory first; the live state is only touched
+    // by the final swap, which also snapshots it for rollback_last_import
+    let staging_dir = create_staging_dir(&app_dir)?;
+    if let Err(e) = stage_backup(&mut archive, &metadata, &app_dir, &staging_dir, options, &renames) {
+        remove_staging_dir(&staging_dir);
+        return Err(e);
+    }
+    
+    // The import replaces the key in the secret store, which the file snapshot doesn't cover
+    let previous_api_key = match seal_api_key(&cached_api_key()) {
+        Ok(sealed) => Some(sealed),
+        Err(e) => {
+            eprintln!("Warning: Rolling back this import won't restore the API key: {}", e);
+            None
+        }
+    };
+    let committed = commit_staged_import(&app_dir, &get_app_config_dir()?, &staging_dir, options.wallpapers, previous_api_key);
+    remove_staging_dir(&staging_dir);
+    committed?;
+    
+    let mut result_message = if options.is_full_restore() {
+        format!("Successfully restored backup created on {}", metadata.created_at)
+    } else {
+        format!("Successfully restored selected items from backup created on {}", metadata.created_at)
+    };
+    
+    let collections_data = match metadata.collections_data {
+        Some(collections_data) if options.collections => {
+            let mut selected = select_collections(collections_data, options, &renames);
+            rewrite_image_paths(&mut selected, &metadata.wallpaper_files, &app_dir.join("wallpapers"), &renames);
+            
+            // Note about collections data (frontend will handle this)
+            result_message.push_str("\nCollections data included - will be restored by frontend.");
+            Some(selected)
+        }
+        _ => None,
+    };
+    
+    if !renames.is_empty() {
+        result_message.push_str(&format!("\n{} collection(s) renamed to avoid ID collisions.", renames.len()));
+    }
+    
+    Ok(ImportResult {
+        message: result_message,
+        collections_data,
+        renamed_collections: renames,
+    })
+}
+
+// The API key to restore: decrypted from the backup, or this machine's current key
+// when the backup was exported without secrets
+fn restored_api_key(metadata: &BackupMetadata, options: &ImportOptions) -> Result<String, String> {
+    if let Some(encrypted) = &metadata.encrypted_secrets {
+        let passphrase = options
+            .passphrase
+            .as_deref()
+            .ok_or("This backup's secrets are encrypted. Enter the backup passphrase to restore them.")?;
+        return Ok(decrypt_secrets(encrypted, passphrase)?.weather_api_key);
+    }
+    
+    if !metadata.secrets_included {
+        return Ok(load_app_settings().weather_api_key);
+    }
+    
+    Ok(metadata.settings.weather_api_key.clone())
+}
+
+// Writes the wallpapers and config files from the backup into the staging directory
+fn stage_backup<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    metadata: &BackupMetadata,
+    app_dir: &Path,
+    staging_dir: &Path,
+    options: &ImportOptions,
+    renames: &HashMap<String, String>,
+) -> Result<(), String> {
+    let wallpapers_dir = staged_wallpapers_dir(staging_dir);
     
-    // Create wallpapers directory if it doesn't exist
-    if !wallpapers_dir.exists() {
-        fs::create_dir_all(&wallpapers_dir)
-            .map_err(|e| format!("Failed to create wallpapers directory: {}", e))?;
+    // Keep existing wallpapers that aren't part of the backup
+    if options.wallpapers {
+        stage_existing_wallpapers(&app_dir.join("wallpapers"), &wallpapers_dir)?;
     }
     
     // Extract wallpaper files
+    let mut extracted_bytes = 0u64;
     for filename in &metadata.wallpaper_files {
+        let restored_name = match restored_wallpaper_name(filename, options, renames) {
+            Some(name) => name,
+            None => continue,
+        };
         let zip_path = format!("wallpapers/{}", filename);
         
         match archive.by_name(&zip_path) {
             Ok(mut file) => {
-                let wallpaper_path = wallpapers_dir.join(filename);
-                let mut buffer = Vec::new();
-                file.read_to_end(&mut buffer)
-                    .map_err(|e| format!("Failed to read {} from ZIP: {}", filename, e))?;
+                let wallpaper_path = wallpaper_path_for(&wallpapers_dir, &restored_name)?;
+                // Preserve the per-collection directory layout
+                if let Some(parent) = wallpaper_path.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create directory for {}: {}", filename, e))?;
+                }
+                
+                // Merging never overwrites images that are already there
+                if wallpaper_path.exists() && options.mode == ImportMode::Merge {
+                    println!("Keeping existing wallpaper {}", restored_name);
+                    continue;
+                }
+                
+                // Replace rather than write through, since staged files may be hard links to live ones
+                if wallpaper_path.exists() {
+                    fs::remove_file(&wallpaper_path)
+                        .map_err(|e| format!("Failed to replace wallpaper {}: {}", filename, e))?;
+                }
                 
-                fs::write(&wallpaper_path, buffer)
+                // The declared size was checked against the limits; a longer stream means it lied
+                let declared_size = file.size().min(MAX_ENTRY_SIZE);
+                let mut output = fs::File::create(&wallpaper_path)
                     .map_err(|e| format!("Failed to write wallpaper {}: {}", filename, e))?;
+                extracted_bytes += copy_limited(&mut file, &mut output, declared_size)
+                    .map_err(|e| format!("Failed to read {} from ZIP: {}", filename, e))?;
+                if extracted_bytes > MAX_TOTAL_SIZE {
+                    return Err("Backup is too large when extracted".to_string());
+                }
             }
             Err(e) => {
+                // Only reachable when importing with force after a failed validation
                 eprintln!("Warning: Failed to extract wallpaper {}: {}", filename, e);
             }
         }
     }
     
-    // Restore settings
-    save_app_settings(&metadata.settings)
-        .map_err(|e| format!("Failed to restore settings: {}", e))?;
+    // Stage settings
+    if options.settings {
+        let settings_json = serde_json::to_string_pretty(&metadata.settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        fs::write(staging_dir.join(SETTINGS_FILE), settings_json)
+            .map_err(|e| format!("Failed to restore settings: {}", e))?;
+    }
     
-    // Restore scheduler config if present
-    if let Some(scheduler_config) = &metadata.scheduler_config {
-        let scheduler_config_path = app_dir.join("scheduler_config.json");
+    // Stage scheduler config if present
+    if let Some(scheduler_config) = metadata.scheduler_config.as_ref().filter(|_| options.scheduler_config) {
         let config_json = serde_json::to_string_pretty(scheduler_config)
             .map_err(|e| format!("Failed to serialize scheduler config: {}", e))?;
         
-        fs::write(scheduler_config_path, config_json)
+        fs::write(staging_dir.join(SCHEDULER_CONFIG_FILE), config_json)
             .map_err(|e| format!("Failed to write scheduler config: {}", e))?;
     }
     
-    let mut result_message = format!("Successfully restored backup created on {}", metadata.created_at);
-    
-    // Note about collections data (frontend will handle this)
-    if metadata.collections_data.is_some() {
-        result_message.push_str("\nCollections data included - will be restored by frontend.");
+    Ok(())
+}
+
+/// Restores settings, scheduler config and wallpapers as they were before the last import.
+#[tauri::command]
+pub async fn rollback_last_import() -> Result<String, String> {
+    let app_dir = get_app_data_dir()?;
+    let snapshot = rollback_to_snapshot(&app_dir, &get_app_config_dir()?)?;
+
+    // The restored settings no longer hold the key once it was migrated, so the key the
+    // import put in the secret store has to be replaced (or removed) explicitly
+    match snapshot.previous_api_key.as_ref().map(unseal_api_key) {
+        Some(Ok(api_key)) => store_api_key(&api_key).await?,
+        Some(Err(e)) => eprintln!("Warning: Failed to restore the API key from before the import: {}", e),
+        None => secure_restored_api_key().await,
     }
-    
-    Ok(result_message)
+    Ok(format!("Restored the state from before the import on {}", snapshot.created_at))
+}
+
+#[tauri::command]
+pub async fn can_rollback_import() -> Result<bool, String> {
+    let app_dir = get_app_data_dir()?;
+    Ok(has_rollback_snapshot(&app_dir))
+}
+
+/// Checks a backup without touching disk: version, missing, corrupt and extra entries.
+#[tauri::command]
+pub async fn validate_backup(zip_data: Vec<u8>) -> Result<BackupValidationReport, String> {
+    let cursor = Cursor::new(zip_data);
+    let mut archive = ZipArchive::new(cursor)
+        .map_err(|e| format!("Invalid ZIP file: {}", e))?;
+
+    Ok(validate_archive(&mut archive))
 }
 
 #[tauri::command]
@@ -202,21 +695,212 @@ pub async fn get_backup_collections_data(zip_data: Vec<u8>) -> Result<Option<Str
         .map_err(|e| format!("Invalid ZIP file: {}", e))?;
     
     // Read metadata
-    let mut metadata_file = archive.by_name("backup.json")
-        .map_err(|e| format!("Backup metadata not found: {}", e))?;
-    
-    let mut metadata_content = String::new();
-    metadata_file.read_to_string(&mut metadata_content)
-        .map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let metadata = read_backup_metadata(&mut archive)?;
     
-    let metadata: BackupMetadata = serde_json::from_str(&metadata_content)
-        .map_err(|e| format!("Invalid metadata format: {}", e))?;
-    
-    // Return collections data as string if present
-    if let Some(collections_data) = metadata.collections_data {
+    // Return collections data as string if present, with image paths pointing at the restored files
+    if let Some(mut collections_data) = metadata.collections_data {
+        let wallpapers_dir = get_app_data_dir()?.join("wallpapers");
+        rewrite_image_paths(&mut collections_data, &metadata.wallpaper_files, &wallpapers_dir, &HashMap::new());
+
         Ok(Some(serde_json::to_string(&collections_data)
             .map_err(|e| format!("Failed to serialize collections data: {}", e))?))
     } else {
         Ok(None)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::backup_integrity::MAX_ARCHIVE_ENTRIES;
+    use crate::modules::utils::{use_test_data_dir, TestDataDir};
+
+    const LOCAL_HEADER: [u8; 4] = [0x50, 0x4b, 0x03, 0x04];
+    const CENTRAL_HEADER: [u8; 4] = [0x50, 0x4b, 0x01, 0x02];
+
+    fn metadata_json(wallpaper_files: &[&str]) -> String {
+        serde_json::json!({
+            "settings": AppSettings::default(),
+            "scheduler_config": null,
+            "collections_data": null,
+            "wallpaper_files": wallpaper_files,
+            "version": "2.4",
+            "created_at": "2026-01-01T00:00:00Z",
+        })
+        .to_string()
+    }
+
+    // A backup as an attacker could craft it: metadata, the given entries and a manifest
+    // whose checksums match them
+    fn build_archive(wallpaper_files: &[&str], entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut manifest = BackupManifest::default();
+
+        let metadata = metadata_json(wallpaper_files);
+        let files = std::iter::once((METADATA_FILE, metadata.as_bytes())).chain(entries.iter().copied());
+        for (name, content) in files {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content).unwrap();
+            manifest.files.push(ManifestEntry {
+                path: name.to_string(),
+                size: content.len() as u64,
+                sha256: checksum_bytes(content),
+            });
+        }
+
+        zip.start_file(MANIFEST_FILE, options).unwrap();
+        zip.write_all(&serde_json::to_vec(&manifest).unwrap()).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    // Rewrites the uncompressed size recorded for `name` in its local and central headers,
+    // leaving the compressed stream alone
+    fn set_declared_size(archive: &mut [u8], name: &str, size: u32) {
+        let mut patched = 0;
+        for (signature, size_offset, name_offset) in [(LOCAL_HEADER, 22, 30), (CENTRAL_HEADER, 24, 46)] {
+            for start in 0..archive.len().saturating_sub(name_offset + name.len()) {
+                let name_range = start + name_offset..start + name_offset + name.len();
+                if archive[start..start + 4] == signature && &archive[name_range] == name.as_bytes() {
+                    archive[start + size_offset..start + size_offset + 4].copy_from_slice(&size.to_le_bytes());
+                    patched += 1;
+                }
+            }
+        }
+        assert_eq!(patched, 2, "headers for {} not found", name);
+    }
+
+    fn validate(data: &[u8]) -> BackupValidationReport {
+        validate_archive(&mut ZipArchive::new(Cursor::new(data.to_vec())).unwrap())
+    }
+
+    fn files_under(dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                files.extend(files_under(&path));
+            } else {
+                files.push(path);
+            }
+        }
+        files
+    }
+
+    // Imports with and without force must fail and leave the test's directories as they were
+    fn assert_rejected(test_dir: &TestDataDir, data: Vec<u8>, forced_too: bool) {
+        let before = files_under(test_dir.outside());
+
+        assert!(!validate(&data).is_valid);
+        let options = ImportOptions::default();
+        for force in [false, true].into_iter().filter(|force| !force || forced_too) {
+            assert!(restore_backup(data.clone(), force, &options).is_err(), "import with force={} succeeded", force);
+        }
+
+        assert_eq!(files_under(test_dir.outside()), before, "import left files behind");
+        let staging_dirs = fs::read_dir(test_dir.path()).unwrap().flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(".import-staging-"))
+            .count();
+        assert_eq!(staging_dirs, 0, "staging directory left behind");
+    }
+
+    #[test]
+    fn rejects_parent_directory_entry() {
+        let test_dir = use_test_data_dir();
+        let data = build_archive(&[], &[("../../.bashrc", b"echo pwned")]);
+        assert!(check_archive_limits(&mut ZipArchive::new(Cursor::new(data.clone())).unwrap()).is_err());
+        assert_rejected(&test_dir, data, true);
+    }
+
+    #[test]
+    fn rejects_absolute_entry() {
+        let test_dir = use_test_data_dir();
+        let target = test_dir.outside().join("escaped.jpg");
+        let target = target.to_string_lossy().to_string();
+        let data = build_archive(&[], &[(target.as_str(), b"image")]);
+        assert_rejected(&test_dir, data, true);
+    }
+
+    #[test]
+    fn rejects_wallpaper_file_escaping_the_wallpapers_directory() {
+        let test_dir = use_test_data_dir();
+        // The archive entry itself looks harmless; the name it's extracted under does not
+        let data = build_archive(&["collection/../../../.bashrc"], &[("wallpapers/collection/x.jpg", b"image")]);
+        let report = validate(&data);
+        assert!(report.errors.iter().any(|e| e.contains("Unsafe path")));
+        assert_rejected(&test_dir, data, true);
+    }
+
+    #[test]
+    fn rejects_absolute_wallpaper_file() {
+        let test_dir = use_test_data_dir();
+        let data = build_archive(&["/etc/passwd"], &[("wallpapers/x.jpg", b"image")]);
+        assert_rejected(&test_dir, data, true);
+    }
+
+    #[test]
+    fn rejects_entry_over_size_limit() {
+        let test_dir = use_test_data_dir();
+        let mut data = build_archive(&["big.jpg"], &[("wallpapers/big.jpg", b"image")]);
+        set_declared_size(&mut data, "wallpapers/big.jpg", (MAX_ENTRY_SIZE + 1) as u32);
+
+        let report = validate(&data);
+        assert!(report.errors.iter().any(|e| e.contains("too large")));
+        assert_rejected(&test_dir, data, true);
+    }
+
+    #[test]
+    fn rejects_too_many_entries() {
+        let test_dir = use_test_data_dir();
+        let names: Vec<String> = (0..=MAX_ARCHIVE_ENTRIES).map(|i| format!("wallpapers/{}.jpg", i)).collect();
+        let entries: Vec<(&str, &[u8])> = names.iter().map(|name| (name.as_str(), &b""[..])).collect();
+        let data = build_archive(&[], &entries);
+
+        let report = validate(&data);
+        assert!(report.errors.iter().any(|e| e.contains("too many entries")));
+        assert_rejected(&test_dir, data, true);
+    }
+
+    #[test]
+    fn rejects_stream_longer_than_declared_size() {
+        let test_dir = use_test_data_dir();
+        let content = vec![0u8; 64 * 1024];
+        let mut data = build_archive(&["liar.jpg"], &[("wallpapers/liar.jpg", &content)]);
+        set_declared_size(&mut data, "wallpapers/liar.jpg", 16);
+
+        let mut archive = ZipArchive::new(Cursor::new(data.clone())).unwrap();
+        check_archive_limits(&mut archive).expect("declared sizes are within the limits");
+        let mut entry = archive.by_name("wallpapers/liar.jpg").unwrap();
+        let declared_size = entry.size();
+        assert!(copy_limited(&mut entry, &mut std::io::sink(), declared_size).is_err());
+        drop(entry);
+
+        assert!(validate(&data).corrupt_files.contains(&"wallpapers/liar.jpg".to_string()));
+        assert_rejected(&test_dir, data, true);
+    }
+
+    #[test]
+    fn sanitize_relative_path_accepts_nested_names_only() {
+        assert_eq!(sanitize_relative_path("work/rain_1.jpg").unwrap(), vec!["work", "rain_1.jpg"]);
+        for name in ["", "/abs.jpg", "../x", "a/../b", "a//b", "./a", "a/.", "a\0b"] {
+            assert!(sanitize_relative_path(name).is_err(), "{:?} was accepted", name);
+        }
+
+        // Separators and prefixes only where the platform has them
+        for name in ["a\\b", "C:evil", "..\\x", "rain:stream"] {
+            assert_eq!(sanitize_relative_path(name).is_err(), cfg!(windows), "{:?}", name);
+        }
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn wallpaper_with_colon_in_name_round_trips() {
+        let test_dir = use_test_data_dir();
+        let content = b"image";
+        let data = build_archive(&["work/12:30 rain.jpg"], &[("wallpapers/work/12:30 rain.jpg", content)]);
+
+        assert!(validate(&data).is_valid);
+        restore_backup(data, false, &ImportOptions::default()).unwrap();
+        let restored = test_dir.path().join("wallpapers").join("work").join("12:30 rain.jpg");
+        assert_eq!(fs::read(restored).unwrap(), content);
+    }
+}
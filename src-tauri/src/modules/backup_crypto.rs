@@ -0,0 +1,151 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+// Ceilings for KDF parameters read from a backup, which come from an untrusted file. A few
+// times the defaults leaves room to raise them later without letting a crafted backup
+// allocate gigabytes or spin for minutes before the passphrase is even checked.
+const MAX_MEMORY_KIB: u32 = 4 * Params::DEFAULT_M_COST;
+const MAX_ITERATIONS: u32 = 4 * Params::DEFAULT_T_COST;
+const MAX_PARALLELISM: u32 = 8;
+
+// Secrets kept out of the plaintext settings in a backup
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BackupSecrets {
+    pub weather_api_key: String,
+}
+
+// Passphrase-encrypted BackupSecrets. The KDF parameters are stored alongside so they
+// can be raised later without breaking older backups.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EncryptedSecrets {
+    pub kdf: String, // "argon2id"
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    pub salt: String, // Base64
+    pub cipher: String, // "aes-256-gcm"
+    pub nonce: String, // Base64
+    pub ciphertext: String, // Base64, includes the GCM tag
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], memory_kib: u32, iterations: u32, parallelism: u32) -> Result<[u8; KEY_LEN], String> {
+    let params = Params::new(memory_kib, iterations, parallelism, Some(KEY_LEN))
+        .map_err(|e| format!("Invalid key derivation parameters: {}", e))?;
+
+    let mut key = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive backup key: {}", e))?;
+    Ok(key)
+}
+
+fn check_kdf_limits(encrypted: &EncryptedSecrets) -> Result<(), String> {
+    if encrypted.memory_kib > MAX_MEMORY_KIB
+        || encrypted.iterations > MAX_ITERATIONS
+        || encrypted.parallelism > MAX_PARALLELISM
+    {
+        return Err(format!(
+            "Backup key derivation parameters exceed the supported limits (memory {} KiB, {} iterations, parallelism {})",
+            encrypted.memory_kib, encrypted.iterations, encrypted.parallelism
+        ));
+    }
+    Ok(())
+}
+
+pub fn encrypt_secrets(secrets: &BackupSecrets, passphrase: &str) -> Result<EncryptedSecrets, String> {
+    if passphrase.is_empty() {
+        return Err("Backup passphrase must not be empty".to_string());
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+
+    let (memory_kib, iterations, parallelism) = (Params::DEFAULT_M_COST, Params::DEFAULT_T_COST, Params::DEFAULT_P_COST);
+    let key = derive_key(passphrase, &salt, memory_kib, iterations, parallelism)?;
+
+    let plaintext = serde_json::to_vec(secrets)
+        .map_err(|e| format!("Failed to serialize secrets: {}", e))?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| "Failed to encrypt backup secrets".to_string())?;
+
+    Ok(EncryptedSecrets {
+        kdf: "argon2id".to_string(),
+        memory_kib,
+        iterations,
+        parallelism,
+        salt: BASE64.encode(salt),
+        cipher: "aes-256-gcm".to_string(),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+pub fn decrypt_secrets(encrypted: &EncryptedSecrets, passphrase: &str) -> Result<BackupSecrets, String> {
+    if encrypted.kdf != "argon2id" || encrypted.cipher != "aes-256-gcm" {
+        return Err(format!("Unsupported backup encryption: {} / {}", encrypted.kdf, encrypted.cipher));
+    }
+    check_kdf_limits(encrypted)?;
+
+    let decode = |value: &str| BASE64.decode(value).map_err(|e| format!("Invalid encrypted secrets: {}", e));
+    let salt = decode(&encrypted.salt)?;
+    let nonce = decode(&encrypted.nonce)?;
+    let ciphertext = decode(&encrypted.ciphertext)?;
+    if nonce.len() != 12 {
+        return Err("Invalid encrypted secrets: bad nonce".to_string());
+    }
+
+    let key = derive_key(passphrase, &salt, encrypted.memory_kib, encrypted.iterations, encrypted.parallelism)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+
+    // GCM authentication fails for both a wrong passphrase and tampered data
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| "Incorrect backup passphrase".to_string())?;
+
+    serde_json::from_slice(&plaintext)
+        .map_err(|e| format!("Invalid encrypted secrets: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secrets() -> BackupSecrets {
+        BackupSecrets { weather_api_key: "abc123".to_string() }
+    }
+
+    #[test]
+    fn round_trips_with_the_right_passphrase_only() {
+        let encrypted = encrypt_secrets(&secrets(), "correct horse").unwrap();
+        assert_eq!(decrypt_secrets(&encrypted, "correct horse").unwrap().weather_api_key, "abc123");
+        assert_eq!(decrypt_secrets(&encrypted, "wrong").unwrap_err(), "Incorrect backup passphrase");
+    }
+
+    #[test]
+    fn rejects_oversized_kdf_parameters_before_deriving() {
+        let encrypted = encrypt_secrets(&secrets(), "correct horse").unwrap();
+        let crafted = [
+            EncryptedSecrets { memory_kib: 4 * 1024 * 1024, ..encrypted.clone() },
+            EncryptedSecrets { iterations: u32::MAX, ..encrypted.clone() },
+            EncryptedSecrets { parallelism: 255, ..encrypted.clone() },
+        ];
+
+        for crafted in crafted {
+            // Deriving with 4 GiB or 2^32 passes would take far longer than this allows
+            let started = std::time::Instant::now();
+            let error = decrypt_secrets(&crafted, "correct horse").unwrap_err();
+            assert!(error.contains("exceed the supported limits"), "{}", error);
+            assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::HashSet;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Component, Path};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use zip::ZipArchive;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const METADATA_FILE: &str = "backup.json";
+
+// Limits for untrusted archives, checked against the declared sizes before extraction
+// and enforced again while decompressing in case the headers lie
+pub const MAX_ARCHIVE_ENTRIES: usize = 10_000;
+pub const MAX_ENTRY_SIZE: u64 = 512 * 1024 * 1024; // 512 MiB per wallpaper
+pub const MAX_TOTAL_SIZE: u64 = 8 * 1024 * 1024 * 1024; // 8 GiB uncompressed
+pub const MAX_METADATA_SIZE: u64 = 64 * 1024 * 1024; // backup.json / manifest.json
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ManifestEntry {
+    pub path: String, // Path inside the archive
+    pub size: u64,
+    pub sha256: String, // Lowercase hex
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BackupManifest {
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BackupValidationReport {
+    pub version: Option<String>,
+    pub created_at: Option<String>,
+    pub is_compatible: bool,
+    pub has_manifest: bool, // Backups before 2.3 have no checksums; only presence is verified
+    pub has_encrypted_secrets: bool, // A passphrase is needed to restore the API key
+    pub missing_files: Vec<String>,
+    pub corrupt_files: Vec<String>,
+    pub extra_files: Vec<String>, // In the archive but not in the manifest; reported, not fatal
+    pub errors: Vec<String>,
+    pub is_valid: bool,
+}
+
+impl BackupValidationReport {
+    pub fn summary(&self) -> String {
+        let mut problems = self.errors.clone();
+        if !self.missing_files.is_empty() {
+            problems.push(format!("{} missing file(s): {}", self.missing_files.len(), self.missing_files.join(", ")));
+        }
+        if !self.corrupt_files.is_empty() {
+            problems.push(format!("{} corrupt file(s): {}", self.corrupt_files.len(), self.corrupt_files.join(", ")));
+        }
+        problems.join("; ")
+    }
+}
+
+/// Validates a "/"-separated name from backup metadata and returns its components.
+/// Rejects anything that could resolve outside the directory it is joined onto: absolute
+/// paths, "." / ".." and empty components, and whatever this platform treats as a
+/// separator or prefix inside a component (backslashes and drive letters on Windows).
+/// Other characters are left alone, so a wallpaper named "12:30.jpg" on Linux round-trips.
+pub fn sanitize_relative_path(name: &str) -> Result<Vec<&str>, String> {
+    let unsafe_name = || format!("Unsafe path in backup: {:?}", name);
+
+    if name.is_empty() || name.starts_with('/') || name.contains('\0') {
+        return Err(unsafe_name());
+    }
+
+    let components: Vec<&str> = name.split('/').collect();
+    for component in &components {
+        let mut parsed = Path::new(component).components();
+        let is_plain_name = matches!(
+            (parsed.next(), parsed.next()),
+            (Some(Component::Normal(part)), None) if part == *component
+        );
+        // Windows also opens alternate data streams through "name:stream"
+        if !is_plain_name || (cfg!(windows) && component.contains(':')) {
+            return Err(unsafe_name());
+        }
+    }
+
+    Ok(components)
+}
+
+/// Rejects archives with too many entries, entries with unsafe names, or whose declared
+/// uncompressed size is too large.
+pub fn check_archive_limits<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<(), String> {
+    if archive.len() > MAX_ARCHIVE_ENTRIES {
+        return Err(format!(
+            "Backup has too many entries ({}, limit {})",
+            archive.len(),
+            MAX_ARCHIVE_ENTRIES
+        ));
+    }
+
+    let mut total = 0u64;
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index)
+            .map_err(|e| format!("Invalid ZIP entry: {}", e))?;
+
+        // Nothing is extracted by its archive name, but a backup containing one is not ours
+        sanitize_relative_path(file.name().strip_suffix('/').unwrap_or(file.name()))?;
+        if file.size() > MAX_ENTRY_SIZE {
+            return Err(format!("Backup entry {} is too large ({} bytes)", file.name(), file.size()));
+        }
+        total = total.saturating_add(file.size());
+    }
+
+    if total > MAX_TOTAL_SIZE {
+        return Err(format!("Backup is too large when extracted ({} bytes)", total));
+    }
+
+    Ok(())
+}
+
+/// Copies at most `limit` bytes, failing instead of truncating if the source is larger.
+pub fn copy_limited<R: Read, W: Write>(reader: &mut R, writer: &mut W, limit: u64) -> Result<u64, String> {
+    let copied = io::copy(&mut reader.take(limit + 1), writer)
+        .map_err(|e| format!("Failed to extract file: {}", e))?;
+
+    if copied > limit {
+        return Err(format!("Extracted file exceeds the {} byte limit", limit));
+    }
+    Ok(copied)
+}
+
+pub fn is_supported_version(version: &str) -> bool {
+    version.starts_with("2.")
+}
+
+/// Copies `reader` into `writer` while hashing, returning the byte count and SHA-256.
+pub fn copy_with_checksum<R: Read, W: Write>(reader: &mut R, writer: &mut W) -> io::Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut total = 0u64;
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])?;
+        total += read as u64;
+    }
+
+    Ok((total, format!("{:x}", hasher.finalize())))
+}
+
+pub fn checksum_bytes(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+pub fn read_archive_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<String, String> {
+    let mut file = archive.by_name(name)
+        .map_err(|e| format!("{} not found: {}", name, e))?;
+    let mut content = Vec::new();
+    copy_limited(&mut file, &mut content, MAX_METADATA_SIZE)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    String::from_utf8(content)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))
+}
+
+/// Checks a backup archive against its manifest without writing anything to disk:
+/// metadata and version, every listed wallpaper present, and checksums matching.
+pub fn validate_archive<R: Read + Seek>(archive: &mut ZipArchive<R>) -> BackupValidationReport {
+    let mut report = BackupValidationReport::default();
+
+    // Don't decompress anything from an archive that is over the limits
+    if let Err(e) = check_archive_limits(archive) {
+        report.errors.push(e);
+        return report;
+    }
+
+    // Metadata is parsed loosely so a report can still be produced for unknown versions
+    let metadata: Option<Value> = match read_archive_entry(archive, METADATA_FILE) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                report.errors.push(format!("Invalid metadata format: {}", e));
+                None
+            }
+        },
+        Err(e) => {
+            report.errors.push(format!("Backup metadata not found: {}", e));
+            None
+        }
+    };
+
+    if let Some(ref metadata) = metadata {
+        report.version = metadata.get("version").and_then(|v| v.as_str()).map(String::from);
+        report.created_at = metadata.get("created_at").and_then(|v| v.as_str()).map(String::from);
+        report.has_encrypted_secrets = metadata.get("encrypted_secrets").map(|v| !v.is_null()).unwrap_or(false);
+    }
+
+    report.is_compatible = report.version.as_deref().map(is_supported_version).unwrap_or(false);
+    if !report.is_compatible {
+        report.errors.push(format!(
+            "Unsupported backup version: {}",
+            report.version.as_deref().unwrap_or("unknown")
+        ));
+    }
+
+    let archive_entries: HashSet<String> = archive.file_names().map(String::from).collect();
+
+    // Every wallpaper listed in the metadata must be present
+    let listed_wallpapers: Vec<String> = metadata
+        .as_ref()
+        .and_then(|m| m.get("wallpaper_files"))
+        .and_then(|files| files.as_array())
+        .map(|files| files.iter().filter_map(|f| f.as_str()).map(|f| format!("wallpapers/{}", f)).collect())
+        .unwrap_or_default();
+
+    for path in &listed_wallpapers {
+        if let Err(e) = sanitize_relative_path(path) {
+            report.errors.push(e);
+        } else if !archive_entries.contains(path) {
+            report.missing_files.push(path.clone());
+        }
+    }
+
+    let manifest = match read_archive_entry(archive, MANIFEST_FILE) {
+        Ok(content) => match serde_json::from_str::<BackupManifest>(&content) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                report.errors.push(format!("Invalid manifest: {}", e));
+                None
+            }
+        },
+        Err(_) => None,
+    };
+
+    if let Some(manifest) = manifest {
+        report.has_manifest = true;
+        let mut manifest_paths = HashSet::new();
+
+        for entry in &manifest.files {
+            manifest_paths.insert(entry.path.clone());
+
+            let mut file = match archive.by_name(&entry.path) {
+                Ok(file) => file,
+                Err(_) => {
+                    if !report.missing_files.contains(&entry.path) {
+                        report.missing_files.push(entry.path.clone());
+                    }
+                    continue;
+                }
+            };
+
+            // Reading one byte past the declared size catches a stream longer than its header says
+            let declared_size = file.size();
+            match copy_with_checksum(&mut (&mut file).take(declared_size.min(MAX_ENTRY_SIZE) + 1), &mut io::sink()) {
+                Ok((size, sha256)) if size == declared_size && size == entry.size && sha256 == entry.sha256 => {}
+                _ => report.corrupt_files.push(entry.path.clone()),
+            }
+        }
+
+        for name in &archive_entries {
+            if name != MANIFEST_FILE && !name.ends_with('/') && !manifest_paths.contains(name) {
+                report.extra_files.push(name.clone());
+            }
+        }
+        report.extra_files.sort();
+    }
+
+    report.is_valid = report.errors.is_empty()
+        && report.missing_files.is_empty()
+        && report.corrupt_files.is_empty();
+    report
+}
@@ -0,0 +1,172 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    #[default]
+    Replace, // Restored collections overwrite those with the same ID
+    Merge, // Restored collections are added alongside existing ones, renamed on ID collision
+}
+
+// Which parts of a backup to restore. Everything is restored by default.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ImportOptions {
+    #[serde(default = "default_true")]
+    pub settings: bool,
+    #[serde(default = "default_true")]
+    pub scheduler_config: bool,
+    #[serde(default = "default_true")]
+    pub collections: bool, // Collections data returned to the frontend
+    #[serde(default = "default_true")]
+    pub wallpapers: bool, // Image files
+    #[serde(default)]
+    pub collection_ids: Option<Vec<String>>, // Only these collections (data and images); None for all
+    #[serde(default)]
+    pub mode: ImportMode,
+    #[serde(default)]
+    pub existing_collection_ids: Vec<String>, // Collections the frontend already has, to detect collisions
+    #[serde(default)]
+    pub passphrase: Option<String>, // For backups with encrypted secrets
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            settings: true,
+            scheduler_config: true,
+            collections: true,
+            wallpapers: true,
+            collection_ids: None,
+            mode: ImportMode::Replace,
+            existing_collection_ids: Vec::new(),
+            passphrase: None,
+        }
+    }
+}
+
+impl ImportOptions {
+    // A full replace of everything, which is what import_backup has always done
+    pub fn is_full_restore(&self) -> bool {
+        self.settings
+            && self.scheduler_config
+            && self.collections
+            && self.wallpapers
+            && self.collection_ids.is_none()
+            && self.mode == ImportMode::Replace
+    }
+
+    fn includes_collection(&self, collection_id: &str) -> bool {
+        self.collection_ids
+            .as_ref()
+            .map(|ids| ids.iter().any(|id| id == collection_id))
+            .unwrap_or(true)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportResult {
+    pub message: String,
+    pub collections_data: Option<Value>, // IDs and image paths already remapped for this machine
+    pub renamed_collections: HashMap<String, String>, // Backup ID -> new ID
+}
+
+fn new_collection_id() -> String {
+    // Same shape as the IDs the frontend generates
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    format!("collection_{}_{}", chrono::Utc::now().timestamp_millis(), &suffix[..9])
+}
+
+/// New IDs for restored collections that would collide with existing ones in merge mode.
+pub fn plan_collection_renames(collections_data: Option<&Value>, options: &ImportOptions) -> HashMap<String, String> {
+    let mut renames = HashMap::new();
+    if options.mode != ImportMode::Merge {
+        return renames;
+    }
+
+    let existing: HashSet<&str> = options.existing_collection_ids.iter().map(String::as_str).collect();
+    let backup_ids = collections_data
+        .and_then(|data| data.get("collections"))
+        .and_then(|c| c.as_object())
+        .map(|collections| collections.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    for id in backup_ids {
+        if options.includes_collection(&id) && existing.contains(id.as_str()) {
+            renames.insert(id, new_collection_id());
+        }
+    }
+
+    renames
+}
+
+/// Applies collection renames to a "/"-separated wallpaper name, whose first component
+/// is the collection ID for per-collection wallpapers.
+pub fn rename_wallpaper_path(relative_name: &str, renames: &HashMap<String, String>) -> String {
+    match relative_name.split_once('/') {
+        Some((collection_id, rest)) => match renames.get(collection_id) {
+            Some(new_id) => format!("{}/{}", new_id, rest),
+            None => relative_name.to_string(),
+        },
+        None => relative_name.to_string(),
+    }
+}
+
+/// Name to restore a backed-up wallpaper under, or None if the options exclude it.
+/// Flat files from older backups don't belong to a collection and are skipped when
+/// specific collections are selected.
+pub fn restored_wallpaper_name(relative_name: &str, options: &ImportOptions, renames: &HashMap<String, String>) -> Option<String> {
+    if !options.wallpapers {
+        return None;
+    }
+
+    if options.collection_ids.is_some() {
+        let (collection_id, _) = relative_name.split_once('/')?;
+        if !options.includes_collection(collection_id) {
+            return None;
+        }
+    }
+
+    Some(rename_wallpaper_path(relative_name, renames))
+}
+
+/// Reduces the backup's collections data to the selected collections under their final IDs.
+/// In merge mode the frontend keeps its active collection, so activeCollectionId is dropped.
+pub fn select_collections(collections_data: Value, options: &ImportOptions, renames: &HashMap<String, String>) -> Value {
+    let mut selected = collections_data;
+
+    if let Some(collections) = selected.get_mut("collections").and_then(|c| c.as_object_mut()) {
+        let restored: serde_json::Map<String, Value> = std::mem::take(collections)
+            .into_iter()
+            .filter(|(id, _)| options.includes_collection(id))
+            .map(|(id, mut collection)| {
+                let final_id = renames.get(&id).cloned().unwrap_or(id);
+                if let Some(fields) = collection.as_object_mut() {
+                    fields.insert("id".to_string(), Value::String(final_id.clone()));
+                }
+                (final_id, collection)
+            })
+            .collect();
+        *collections = restored;
+    }
+
+    let active_id = selected
+        .get("activeCollectionId")
+        .and_then(|id| id.as_str())
+        .filter(|id| options.mode == ImportMode::Replace && options.includes_collection(id))
+        .map(String::from);
+
+    if let Some(data) = selected.as_object_mut() {
+        data.insert(
+            "activeCollectionId".to_string(),
+            active_id.map(Value::String).unwrap_or(Value::Null),
+        );
+    }
+
+    selected
+}
@@ -0,0 +1,240 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::modules::persistence::{lock_file, write_json_atomic};
+
+// Files an import may replace, relative to the app config directory
+pub const SETTINGS_FILE: &str = "app_settings.json";
+pub const SCHEDULER_CONFIG_FILE: &str = "scheduler_config.json";
+const RESTORABLE_FILES: [&str; 2] = [SETTINGS_FILE, SCHEDULER_CONFIG_FILE];
+const WALLPAPERS_DIR: &str = "wallpapers";
+
+const ROLLBACK_DIR: &str = "import_rollback";
+const ROLLBACK_INFO_FILE: &str = "snapshot.json";
+
+// Describes what existed before the last import so rollback can restore it exactly
+#[derive(Serialize, Deserialize, Debug)]
+struct RollbackSnapshot {
+    created_at: String,
+    existing_files: Vec<String>, // Restorable files that existed before the import
+    had_wallpapers: bool,
+    replaced_wallpapers: bool, // Whether the import swapped the wallpapers directory
+    // The secret-store API key before the import, sealed with this machine's key. Missing
+    // in snapshots from older versions.
+    #[serde(default)]
+    previous_api_key: Option<Value>,
+}
+
+/// What `rollback_to_snapshot` put back
+pub struct RestoredSnapshot {
+    pub created_at: String,
+    pub previous_api_key: Option<Value>, // To hand back to the secret store, which isn't a file here
+}
+
+/// Creates an empty staging directory inside the app data directory, so the final
+/// swap is a same-filesystem rename.
+pub fn create_staging_dir(app_dir: &Path) -> Result<PathBuf, String> {
+    let staging_dir = app_dir.join(format!(".import-staging-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(staging_dir.join(WALLPAPERS_DIR))
+        .map_err(|e| format!("Failed to create import staging directory: {}", e))?;
+    Ok(staging_dir)
+}
+
+pub fn remove_staging_dir(staging_dir: &Path) {
+    if let Err(e) = fs::remove_dir_all(staging_dir) {
+        eprintln!("Warning: Failed to clean up import staging directory: {}", e);
+    }
+}
+
+pub fn staged_wallpapers_dir(staging_dir: &Path) -> PathBuf {
+    staging_dir.join(WALLPAPERS_DIR)
+}
+
+/// Mirrors the current wallpapers into the staging area (hard links where possible) so
+/// wallpapers not contained in the backup survive the directory swap.
+pub fn stage_existing_wallpapers(current_dir: &Path, staged_dir: &Path) -> Result<(), String> {
+    if !current_dir.exists() {
+        return Ok(());
+    }
+
+    let entries = fs::read_dir(current_dir)
+        .map_err(|e| format!("Failed to read wallpapers directory: {}", e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let source = entry.path();
+        let target = staged_dir.join(entry.file_name());
+
+        if source.is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create staged directory: {}", e))?;
+            stage_existing_wallpapers(&source, &target)?;
+        } else if source.is_file() && fs::hard_link(&source, &target).is_err() {
+            fs::copy(&source, &target)
+                .map_err(|e| format!("Failed to stage wallpaper {}: {}", source.display(), e))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn move_back(from: &Path, to: &Path) {
+    if from.exists() {
+        if let Err(e) = fs::rename(from, to) {
+            eprintln!("Failed to restore {}: {}", to.display(), e);
+        }
+    }
+}
+
+// Config files live in the config directory, possibly on another filesystem than the
+// staging area, so they're copied over with an atomic replace rather than renamed
+fn replace_config_file(staged: &Path, target: &Path) -> Result<(), String> {
+    let content = fs::read(staged).map_err(|e| e.to_string())?;
+    lock_file(target)?.write(&content)
+}
+
+/// Snapshots the current state for `rollback_last_import`, then moves the staged
+/// files and wallpapers into place. Each replacement is atomic; if any step fails
+/// the already-swapped pieces are put back. `previous_api_key` is kept in the snapshot
+/// as given.
+pub fn commit_staged_import(
+    app_dir: &Path,
+    config_dir: &Path,
+    staging_dir: &Path,
+    replace_wallpapers: bool,
+    previous_api_key: Option<Value>,
+) -> Result<(), String> {
+    let new_snapshot_dir = app_dir.join(format!("{}.new", ROLLBACK_DIR));
+    if new_snapshot_dir.exists() {
+        fs::remove_dir_all(&new_snapshot_dir)
+            .map_err(|e| format!("Failed to clear previous snapshot: {}", e))?;
+    }
+    fs::create_dir_all(&new_snapshot_dir)
+        .map_err(|e| format!("Failed to create import snapshot: {}", e))?;
+
+    let current_wallpapers = app_dir.join(WALLPAPERS_DIR);
+    let mut snapshot = RollbackSnapshot {
+        created_at: chrono::Utc::now().to_rfc3339(),
+        existing_files: Vec::new(),
+        had_wallpapers: current_wallpapers.exists(),
+        replaced_wallpapers: replace_wallpapers,
+        previous_api_key,
+    };
+
+    for name in RESTORABLE_FILES {
+        let current = config_dir.join(name);
+        if current.exists() {
+            fs::copy(&current, new_snapshot_dir.join(name))
+                .map_err(|e| format!("Failed to snapshot {}: {}", name, e))?;
+            snapshot.existing_files.push(name.to_string());
+        }
+    }
+
+    // Swap in the wallpapers first; the old directory becomes part of the snapshot
+    let snapshot_wallpapers = new_snapshot_dir.join(WALLPAPERS_DIR);
+    if replace_wallpapers {
+        if current_wallpapers.exists() {
+            fs::rename(&current_wallpapers, &snapshot_wallpapers)
+                .map_err(|e| format!("Failed to snapshot wallpapers: {}", e))?;
+        }
+        if let Err(e) = fs::rename(staged_wallpapers_dir(staging_dir), &current_wallpapers) {
+            move_back(&snapshot_wallpapers, &current_wallpapers);
+            return Err(format!("Failed to move restored wallpapers into place: {}", e));
+        }
+    }
+
+    // Then the config files, rolling everything back if one fails
+    for name in RESTORABLE_FILES {
+        let staged = staging_dir.join(name);
+        if !staged.exists() {
+            continue;
+        }
+        if let Err(e) = replace_config_file(&staged, &config_dir.join(name)) {
+            restore_snapshot(app_dir, config_dir, &new_snapshot_dir, &snapshot);
+            return Err(format!("Failed to move restored {} into place: {}", name, e));
+        }
+    }
+
+    // Rollback depends on this file, so it's never left half-written
+    write_json_atomic(&new_snapshot_dir.join(ROLLBACK_INFO_FILE), &snapshot)
+        .map_err(|e| format!("Failed to write snapshot info: {}", e))?;
+
+    // Only now replace the previous snapshot, so a failed import never loses it
+    let snapshot_dir = app_dir.join(ROLLBACK_DIR);
+    if snapshot_dir.exists() {
+        fs::remove_dir_all(&snapshot_dir)
+            .map_err(|e| format!("Failed to remove old snapshot: {}", e))?;
+    }
+    fs::rename(&new_snapshot_dir, &snapshot_dir)
+        .map_err(|e| format!("Failed to store import snapshot: {}", e))?;
+
+    Ok(())
+}
+
+// Puts the snapshotted files and wallpapers back in place
+fn restore_snapshot(app_dir: &Path, config_dir: &Path, snapshot_dir: &Path, snapshot: &RollbackSnapshot) {
+    for name in RESTORABLE_FILES {
+        let target = config_dir.join(name);
+        if snapshot.existing_files.iter().any(|f| f == name) {
+            if let Err(e) = replace_config_file(&snapshot_dir.join(name), &target) {
+                eprintln!("Failed to restore {}: {}", name, e);
+            }
+        } else if target.exists() {
+            let _ = fs::remove_file(&target);
+        }
+    }
+
+    if snapshot.replaced_wallpapers {
+        let current_wallpapers = app_dir.join(WALLPAPERS_DIR);
+        if current_wallpapers.exists() {
+            let _ = fs::remove_dir_all(&current_wallpapers);
+        }
+        move_back(&snapshot_dir.join(WALLPAPERS_DIR), &current_wallpapers);
+    }
+}
+
+pub fn has_rollback_snapshot(app_dir: &Path) -> bool {
+    app_dir.join(ROLLBACK_DIR).join(ROLLBACK_INFO_FILE).exists()
+}
+
+/// Restores the files captured before the last import and discards the snapshot.
+pub fn rollback_to_snapshot(app_dir: &Path, config_dir: &Path) -> Result<RestoredSnapshot, String> {
+    let snapshot_dir = app_dir.join(ROLLBACK_DIR);
+    let info = fs::read_to_string(snapshot_dir.join(ROLLBACK_INFO_FILE))
+        .map_err(|_| "No import to roll back".to_string())?;
+    let snapshot: RollbackSnapshot = serde_json::from_str(&info)
+        .map_err(|e| format!("Invalid import snapshot: {}", e))?;
+
+    restore_snapshot(app_dir, config_dir, &snapshot_dir, &snapshot);
+
+    fs::remove_dir_all(&snapshot_dir)
+        .map_err(|e| format!("Restored, but failed to remove snapshot: {}", e))?;
+
+    Ok(RestoredSnapshot {
+        created_at: snapshot.created_at,
+        previous_api_key: snapshot.previous_api_key,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rollback_returns_the_key_captured_at_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let (app_dir, config_dir) = (dir.path().join("data"), dir.path().join("config"));
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(config_dir.join(SETTINGS_FILE), r#"{"weather_api_key": ""}"#).unwrap();
+
+        let staging_dir = create_staging_dir(&app_dir).unwrap();
+        fs::write(staging_dir.join(SETTINGS_FILE), r#"{"weather_api_key": ""}"#).unwrap();
+        let sealed = serde_json::json!({ "nonce": "n", "ciphertext": "c" });
+        commit_staged_import(&app_dir, &config_dir, &staging_dir, false, Some(sealed.clone())).unwrap();
+
+        let restored = rollback_to_snapshot(&app_dir, &config_dir).unwrap();
+        assert_eq!(restored.previous_api_key, Some(sealed));
+        assert!(!has_rollback_snapshot(&app_dir));
+    }
+}
@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::Emitter;
+use crate::modules::notifications::get_app_handle;
+
+/// Upgrades a config document from version N to N+1. `migrations[n]` runs on documents at version n.
+pub type Migration = fn(&mut Value) -> Result<(), String>;
+
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+// A config file that couldn't be loaded as-is, reported to the UI until dismissed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConfigLoadError {
+    pub file: String,
+    pub message: String,
+    pub backup_path: Option<String>, // Copy of the file as it was before recovery
+    pub occurred_at: String,
+}
+
+static CONFIG_ERRORS: Mutex<Vec<ConfigLoadError>> = Mutex::new(Vec::new());
+
+pub struct LoadedConfig<T> {
+    pub value: T,
+    pub needs_save: bool, // Migrated or recovered; the caller should write it back
+}
+
+/// Loads a versioned JSON config file, running any pending migrations in order.
+///
+/// Nothing is silently discarded: if the file can't be parsed or some fields are invalid,
+/// the original is copied aside, every field that still deserializes is kept, and the
+/// problem is reported through `get_config_errors`.
+pub fn load_versioned<T>(path: &Path, current_version: u32, migrations: &[Migration]) -> LoadedConfig<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return LoadedConfig { value: T::default(), needs_save: false },
+    };
+
+    let mut document: Value = match serde_json::from_str(&content) {
+        Ok(document) => document,
+        Err(e) => {
+            report_error(path, format!("File is not valid JSON ({}); defaults were used", e));
+            return LoadedConfig { value: T::default(), needs_save: true };
+        }
+    };
+
+    let version = document
+        .get(SCHEMA_VERSION_FIELD)
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32;
+
+    let mut needs_save = false;
+    if version > current_version {
+        // Written by a newer version of the app; load what we understand but don't write it back
+        eprintln!(
+            "Warning: {} has schema version {}, newer than supported version {}",
+            path.display(), version, current_version
+        );
+    } else if version < current_version {
+        for (index, migration) in migrations.iter().enumerate().skip(version as usize) {
+            if let Err(e) = migration(&mut document) {
+                report_error(path, format!("Migration from schema version {} failed: {}", index, e));
+                return LoadedConfig { value: T::default(), needs_save: true };
+            }
+        }
+        if let Some(fields) = document.as_object_mut() {
+            fields.insert(SCHEMA_VERSION_FIELD.to_string(), Value::from(current_version));
+        }
+        println!("Migrated {} from schema version {} to {}", path.display(), version, current_version);
+        needs_save = true;
+    }
+
+    match serde_json::from_value::<T>(document.clone()) {
+        Ok(value) => LoadedConfig { value, needs_save },
+        Err(e) => {
+            let (value, dropped) = recover_fields::<T>(&document);
+            report_error(path, format!(
+                "Some settings were invalid and were reset to defaults ({}): {}",
+                e,
+                if dropped.is_empty() { "unknown fields".to_string() } else { dropped.join(", ") }
+            ));
+            LoadedConfig { value, needs_save: true }
+        }
+    }
+}
+
+// Starts from the defaults and applies each field from the document that deserializes on
+// its own, returning the names of the fields that had to be dropped
+fn recover_fields<T>(document: &Value) -> (T, Vec<String>)
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    let defaults = serde_json::to_value(T::default()).unwrap_or(Value::Null);
+    let (fields, mut recovered) = match (document.as_object(), defaults.as_object()) {
+        (Some(fields), Some(defaults)) => (fields, defaults.clone()),
+        _ => return (T::default(), Vec::new()),
+    };
+
+    let mut dropped = Vec::new();
+    for (name, field_value) in fields {
+        let previous = recovered.insert(name.clone(), field_value.clone());
+        if serde_json::from_value::<T>(Value::Object(recovered.clone())).is_err() {
+            match previous {
+                Some(previous) => recovered.insert(name.clone(), previous),
+                None => recovered.remove(name),
+            };
+            dropped.push(name.clone());
+        }
+    }
+
+    let value = serde_json::from_value(Value::Object(recovered)).unwrap_or_default();
+    (value, dropped)
+}
+
+// Copies the unreadable file aside and records the error for the UI
+fn report_error(path: &Path, message: String) {
+    let backup_path = backup_unreadable_file(path);
+    record_error(path, message, backup_path);
+}
+
+/// Reports a problem with a config file the app doesn't own, so nothing is copied aside.
+pub fn record_config_error(path: &Path, message: String) {
+    record_error(path, message, None);
+}
+
+fn record_error(path: &Path, message: String, backup_path: Option<PathBuf>) {
+    eprintln!("Failed to load {}: {}", path.display(), message);
+
+    let error = ConfigLoadError {
+        file: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        message,
+        backup_path: backup_path.map(|p| p.to_string_lossy().to_string()),
+        occurred_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    if let Some(app_handle) = get_app_handle() {
+        let _ = app_handle.emit("config-load-error", error.clone());
+    }
+    CONFIG_ERRORS.lock().unwrap().push(error);
+}
+
+fn backup_unreadable_file(path: &Path) -> Option<PathBuf> {
+    let stem = path.file_stem()?.to_string_lossy().to_string();
+    let backup_path = path.with_file_name(format!(
+        "{}.unreadable-{}.json",
+        stem,
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    match fs::copy(path, &backup_path) {
+        Ok(_) => Some(backup_path),
+        Err(e) => {
+            eprintln!("Failed to back up {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Fills in any of `defaults` missing from the top level of a document. Useful for
+/// migrations that make a previously required field optional.
+pub fn insert_missing_fields(document: &mut Value, defaults: Value) -> Result<(), String> {
+    let fields = document.as_object_mut().ok_or("Expected a JSON object")?;
+    if let Value::Object(defaults) = defaults {
+        for (name, value) in defaults {
+            fields.entry(name).or_insert(value);
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_config_errors() -> Result<Vec<ConfigLoadError>, String> {
+    Ok(CONFIG_ERRORS.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn dismiss_config_errors() -> Result<(), String> {
+    CONFIG_ERRORS.lock().unwrap().clear();
+    Ok(())
+}
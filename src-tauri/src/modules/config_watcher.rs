@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use notify::{Event, RecursiveMode, Watcher};
+use tauri::Emitter;
+use tokio::sync::mpsc;
+use crate::modules::declarative_config::{reload_declarative_config, CONFIG_FILE as DECLARATIVE_CONFIG_FILE};
+use crate::modules::location::resolve_location_query;
+use crate::modules::notifications::get_app_handle;
+use crate::modules::scheduler::{
+    last_applied_wallpaper, refresh_declared_collections, reload_scheduler_config, request_wallpaper_refresh,
+};
+use crate::modules::settings::{load_app_settings, AppSettings};
+use crate::modules::utils::{get_app_config_dir, get_app_data_dir, get_location_key};
+use crate::modules::weather_cache::remove_cache_entry;
+
+const SETTINGS_FILE: &str = "app_settings.json";
+const SCHEDULER_CONFIG_FILE: &str = "scheduler_config.json";
+const WALLPAPERS_DIR: &str = "wallpapers";
+
+// Editors and atomic saves produce bursts of events for a single change; wait for them to settle
+const DEBOUNCE_MILLIS: u64 = 500;
+
+// Settings as of the last reload, to tell real changes from rewrites of the same values
+static LAST_SETTINGS: Mutex<Option<AppSettings>> = Mutex::new(None);
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+enum WatchedFile {
+    DeclarativeConfig,
+    Settings,
+    SchedulerConfig,
+    Wallpapers,
+}
+
+fn classify(config_dir: &Path, wallpapers_dir: &Path, path: &Path) -> Option<WatchedFile> {
+    if path.starts_with(wallpapers_dir) {
+        return Some(WatchedFile::Wallpapers);
+    }
+    if path.parent() != Some(config_dir) {
+        return None;
+    }
+
+    // Lock and temp files from the persistence layer have other names and are ignored
+    match path.file_name()?.to_str()? {
+        SETTINGS_FILE => Some(WatchedFile::Settings),
+        SCHEDULER_CONFIG_FILE => Some(WatchedFile::SchedulerConfig),
+        DECLARATIVE_CONFIG_FILE => Some(WatchedFile::DeclarativeConfig),
+        _ => None,
+    }
+}
+
+fn emit(event: &str) {
+    if let Some(app_handle) = get_app_handle() {
+        let _ = app_handle.emit(event, ());
+    }
+}
+
+fn settings_changed(previous: &AppSettings, current: &AppSettings) -> bool {
+    serde_json::to_value(previous).ok() != serde_json::to_value(current).ok()
+}
+
+fn reload_settings() {
+    let settings = load_app_settings();
+    let previous = LAST_SETTINGS.lock().unwrap().replace(settings.clone());
+
+    let previous = match previous {
+        Some(previous) if settings_changed(&previous, &settings) => previous,
+        _ => return,
+    };
+    println!("Settings changed on disk, reloading");
+
+    // A different location means the cached weather and the current wallpaper no longer apply
+    let previous_location = resolve_location_query(&previous).ok();
+    if previous_location != resolve_location_query(&settings).ok() {
+        if let Some(location) = previous_location {
+            if let Err(e) = remove_cache_entry(&get_location_key(&location)) {
+                eprintln!("Warning: Failed to invalidate weather cache: {}", e);
+            }
+        }
+        request_wallpaper_refresh(false);
+    }
+
+    emit("settings-changed");
+}
+
+fn reload_wallpapers(changed_paths: &HashSet<PathBuf>) {
+    // Re-apply the current wallpaper if its file was replaced; otherwise just re-check, in
+    // case a file a collection points at has appeared or gone away
+    let current_replaced = last_applied_wallpaper()
+        .map(|path| changed_paths.contains(Path::new(&path)))
+        .unwrap_or(false);
+    request_wallpaper_refresh(current_replaced);
+
+    emit("wallpapers-changed");
+}
+
+fn apply_changes(changed: &HashSet<WatchedFile>, changed_wallpapers: &HashSet<PathBuf>) {
+    // config.toml overrides both JSON files, so a change to it is applied through them
+    let declarative_changed = changed.contains(&WatchedFile::DeclarativeConfig);
+    if declarative_changed {
+        println!("config.toml changed, reloading");
+        reload_declarative_config();
+        emit("declarative-config-changed");
+    }
+
+    if declarative_changed || changed.contains(&WatchedFile::Settings) {
+        reload_settings();
+    }
+    if (declarative_changed || changed.contains(&WatchedFile::SchedulerConfig)) && reload_scheduler_config() {
+        emit("scheduler-config-changed");
+    }
+    if declarative_changed {
+        // Declared collections may point the scheduler at different wallpapers
+        refresh_declared_collections();
+        request_wallpaper_refresh(false);
+    }
+    if changed.contains(&WatchedFile::Wallpapers) {
+        reload_wallpapers(changed_wallpapers);
+    }
+}
+
+/// Watches the settings file, the scheduler config, config.toml and the wallpapers directory, applying
+/// changes made outside the app (by hand or by a dotfile manager) without a restart.
+pub async fn config_watcher_loop() {
+    let (config_dir, data_dir) = match (get_app_config_dir(), get_app_data_dir()) {
+        (Ok(config_dir), Ok(data_dir)) => (config_dir, data_dir),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Config watcher not started: {}", e);
+            return;
+        }
+    };
+    let wallpapers_dir = data_dir.join(WALLPAPERS_DIR);
+    if let Err(e) = fs::create_dir_all(&wallpapers_dir) {
+        eprintln!("Warning: Failed to create wallpapers directory: {}", e);
+    }
+
+    let (sender, mut receiver) = mpsc::unbounded_channel::<notify::Result<Event>>();
+    let mut watcher = match notify::recommended_watcher(move |result| {
+        let _ = sender.send(result);
+    }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("Config watcher not started: {}", e);
+            return;
+        }
+    };
+
+    // Watch the directory rather than the files, since atomic saves replace them and
+    // dotfile managers swap config.toml symlinks
+    if let Err(e) = watcher.watch(&config_dir, RecursiveMode::NonRecursive) {
+        eprintln!("Config watcher not started: {}", e);
+        return;
+    }
+    if let Err(e) = watcher.watch(&wallpapers_dir, RecursiveMode::Recursive) {
+        eprintln!("Warning: Not watching wallpapers directory: {}", e);
+    }
+
+    *LAST_SETTINGS.lock().unwrap() = Some(load_app_settings());
+    println!("Watching {} for configuration changes", config_dir.display());
+
+    while let Some(first) = receiver.recv().await {
+        let mut changed = HashSet::new();
+        let mut changed_wallpapers = HashSet::new();
+        let mut next = Some(first);
+
+        while let Some(result) = next {
+            match result {
+                // Reads show up as access events; only changes matter
+                Ok(event) if !event.kind.is_access() && !event.kind.is_other() => {
+                    for path in &event.paths {
+                        if let Some(file) = classify(&config_dir, &wallpapers_dir, path) {
+                            changed.insert(file);
+                            if file == WatchedFile::Wallpapers {
+                                changed_wallpapers.insert(path.clone());
+                            }
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Config watcher error: {}", e),
+            }
+
+            next = tokio::time::timeout(Duration::from_millis(DEBOUNCE_MILLIS), receiver.recv())
+                .await
+                .ok()
+                .flatten();
+        }
+
+        if !changed.is_empty() {
+            apply_changes(&changed, &changed_wallpapers);
+        }
+    }
+}
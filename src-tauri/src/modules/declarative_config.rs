@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::modules::config_migration::record_config_error;
+use crate::modules::settings::AppSettings;
+use crate::modules::utils::get_app_config_dir;
+
+// Optional, hand-written configuration for dotfile-managed setups (Nix/home-manager, chezmoi).
+// Anything declared here overrides the JSON state the app writes and can't be changed in the UI:
+//
+//   active_collection = "work"
+//
+//   [settings]
+//   location = "Berlin"
+//   cache_duration_minutes = 30
+//
+//   [scheduler]
+//   enabled = true
+//   interval_minutes = 5
+//
+//   [collections.work]
+//   name = "Work"
+//   rules.default = { image = "~/Pictures/default.jpg" }
+//   rules.rain = { image = "~/Pictures/rain.jpg", priority = 95, lock_screen = true }
+pub const CONFIG_FILE: &str = "config.toml";
+
+// Secrets don't belong in dotfiles, and the schema version is the app's own bookkeeping
+const UNDECLARABLE_SETTINGS: [&str; 2] = ["weather_api_key", "schema_version"];
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct DeclarativeConfig {
+    #[serde(default)]
+    settings: Map<String, Value>,
+    #[serde(default)]
+    scheduler: DeclaredScheduler,
+    #[serde(default)]
+    active_collection: Option<String>,
+    #[serde(default)]
+    collections: BTreeMap<String, DeclaredCollection>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct DeclaredScheduler {
+    pub enabled: Option<bool>,
+    pub interval_minutes: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeclaredCollection {
+    name: Option<String>,
+    #[serde(default)]
+    rules: BTreeMap<String, DeclaredRule>, // Keyed by category
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeclaredRule {
+    image: Option<String>, // "~" and paths relative to the config directory are resolved
+    enabled: Option<bool>,
+    priority: Option<i32>,
+    lock_screen: Option<bool>, // Also use the image on the lock screen
+}
+
+// What config.toml declares, as reported to the frontend
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct DeclarativeConfigStatus {
+    pub path: Option<String>,
+    pub loaded: bool,
+    pub error: Option<String>,
+    pub settings: Map<String, Value>,
+    pub scheduler: DeclaredScheduler,
+    pub active_collection: Option<String>,
+    pub collections: Map<String, Value>, // Same shape as the frontend's collections, settings limited to declared rules
+}
+
+static DECLARED: Mutex<Option<DeclarativeConfigStatus>> = Mutex::new(None);
+
+pub fn get_config_file_path() -> Option<PathBuf> {
+    Some(get_app_config_dir().ok()?.join(CONFIG_FILE))
+}
+
+fn expand_image_path(image: &str, config_dir: &Path) -> String {
+    let path = match image.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map(|home| home.join(rest)).unwrap_or_else(|| PathBuf::from(image)),
+        None => config_dir.join(image), // Absolute paths replace the base
+    };
+    path.to_string_lossy().to_string()
+}
+
+fn collection_to_json(id: &str, collection: &DeclaredCollection, config_dir: &Path) -> Value {
+    let settings: Map<String, Value> = collection
+        .rules
+        .iter()
+        .map(|(category, rule)| {
+            let mut setting = Map::new();
+            setting.insert("category".to_string(), Value::from(category.as_str()));
+            setting.insert(
+                "imagePath".to_string(),
+                rule.image.as_deref().map(|image| Value::from(expand_image_path(image, config_dir))).unwrap_or(Value::Null),
+            );
+            setting.insert("enabled".to_string(), Value::from(rule.enabled.unwrap_or(true)));
+            if let Some(priority) = rule.priority {
+                setting.insert("priority".to_string(), Value::from(priority));
+            }
+            if let Some(lock_screen) = rule.lock_screen {
+                setting.insert("lockScreen".to_string(), Value::from(lock_screen));
+            }
+            (category.clone(), Value::Object(setting))
+        })
+        .collect();
+
+    serde_json::json!({
+        "id": id,
+        "name": collection.name.clone().unwrap_or_else(|| id.to_string()),
+        "settings": settings,
+    })
+}
+
+// Declared settings must be known fields that still deserialize into AppSettings
+fn validate_settings(settings: &Map<String, Value>) -> Result<(), String> {
+    let mut document = serde_json::to_value(AppSettings::default())
+        .map_err(|e| format!("Failed to serialize default settings: {}", e))?;
+    let fields = document.as_object_mut().ok_or("Settings are not an object")?;
+
+    for (name, value) in settings {
+        if UNDECLARABLE_SETTINGS.contains(&name.as_str()) {
+            return Err(format!("settings.{} can't be set in {}", name, CONFIG_FILE));
+        }
+        if !fields.contains_key(name) {
+            return Err(format!("Unknown setting settings.{}", name));
+        }
+        fields.insert(name.clone(), value.clone());
+    }
+
+    serde_json::from_value::<AppSettings>(document)
+        .map(|_| ())
+        .map_err(|e| format!("Invalid settings: {}", e))
+}
+
+fn parse_config(content: &str, config_dir: &Path) -> Result<DeclarativeConfigStatus, String> {
+    let config: DeclarativeConfig = toml::from_str(content).map_err(|e| e.to_string())?;
+    validate_settings(&config.settings)?;
+
+    if let Some(active) = &config.active_collection {
+        if !config.collections.contains_key(active) {
+            return Err(format!("active_collection \"{}\" is not declared under [collections]", active));
+        }
+    }
+
+    Ok(DeclarativeConfigStatus {
+        path: None,
+        loaded: true,
+        error: None,
+        settings: config.settings,
+        scheduler: config.scheduler,
+        active_collection: config.active_collection,
+        collections: config
+            .collections
+            .iter()
+            .map(|(id, collection)| (id.clone(), collection_to_json(id, collection, config_dir)))
+            .collect(),
+    })
+}
+
+fn read_config() -> DeclarativeConfigStatus {
+    let path = match get_config_file_path() {
+        Some(path) => path,
+        None => return DeclarativeConfigStatus::default(),
+    };
+    let path_string = Some(path.to_string_lossy().to_string());
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return DeclarativeConfigStatus { path: path_string, ..Default::default() },
+    };
+
+    let config_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    match parse_config(&content, &config_dir) {
+        Ok(status) => DeclarativeConfigStatus { path: path_string, ..status },
+        Err(e) => {
+            // An invalid file is ignored as a whole rather than half-applied
+            record_config_error(&path, format!("{} was not applied: {}", CONFIG_FILE, e));
+            DeclarativeConfigStatus { path: path_string, error: Some(e), ..Default::default() }
+        }
+    }
+}
+
+fn declared() -> DeclarativeConfigStatus {
+    let mut declared = DECLARED.lock().unwrap();
+    declared.get_or_insert_with(read_config).clone()
+}
+
+/// Re-reads config.toml. Called when the file changes on disk.
+pub fn reload_declarative_config() -> DeclarativeConfigStatus {
+    let status = read_config();
+    *DECLARED.lock().unwrap() = Some(status.clone());
+    status
+}
+
+/// Applies declared settings over the ones loaded from app_settings.json.
+pub fn apply_declared_settings(settings: AppSettings) -> AppSettings {
+    let declared = declared();
+    if declared.settings.is_empty() {
+        return settings;
+    }
+
+    let mut document = match serde_json::to_value(&settings) {
+        Ok(document) => document,
+        Err(_) => return settings,
+    };
+    if let Some(fields) = document.as_object_mut() {
+        fields.extend(declared.settings);
+    }
+    serde_json::from_value(document).unwrap_or(settings)
+}
+
+pub fn declared_scheduler() -> DeclaredScheduler {
+    declared().scheduler
+}
+
+/// Merges declared collections into the collections data synced from the frontend.
+pub fn apply_declared_collections(collection_data: &mut Value) {
+    let declared = declared();
+    let data = match collection_data.as_object_mut() {
+        Some(data) => data,
+        None => return,
+    };
+
+    if !declared.collections.is_empty() {
+        let collections = data
+            .entry("collections")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(collections) = collections.as_object_mut() {
+            collections.extend(declared.collections);
+        }
+    }
+    if let Some(active) = declared.active_collection {
+        data.insert("activeCollectionId".to_string(), Value::from(active));
+    }
+}
+
+// Fields of a declared section that are actually set
+fn managed_fields(declared: &Value) -> Vec<String> {
+    declared
+        .as_object()
+        .map(|fields| {
+            fields
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn managed_settings_fields() -> Vec<String> {
+    declared().settings.keys().cloned().collect()
+}
+
+pub fn managed_scheduler_fields() -> Vec<String> {
+    managed_fields(&serde_json::to_value(declared_scheduler()).unwrap_or(Value::Null))
+}
+
+/// Puts the values already in the JSON file back into `document` for fields config.toml
+/// manages, so declared values aren't written into the app's own state and removing them
+/// from config.toml brings the previous values back.
+pub fn keep_file_values(document: &mut Value, path: &Path, managed: &[String]) {
+    if managed.is_empty() {
+        return;
+    }
+
+    let on_disk: Option<Value> = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok());
+    let (fields, on_disk) = match (document.as_object_mut(), on_disk.as_ref().and_then(|v| v.as_object())) {
+        (Some(fields), Some(on_disk)) => (fields, on_disk),
+        _ => return,
+    };
+
+    for name in managed {
+        if let Some(value) = on_disk.get(name) {
+            fields.insert(name.clone(), value.clone());
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_declarative_config() -> Result<DeclarativeConfigStatus, String> {
+    Ok(declared())
+}
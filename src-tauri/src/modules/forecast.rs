@@ -0,0 +1,155 @@
+use chrono::{DateTime as ChronoDateTime, Local, Utc};
+use crate::modules::types::{AstroData, CurrentConditions, ForecastHour, ForecastTimelineEntry, ForecastTimelineResponse, WeatherApiResponse};
+use crate::modules::time::get_time_period_at;
+use crate::modules::air_quality::get_air_quality_categories;
+use crate::modules::moon::{get_moon_categories, moon_illumination_for, moon_phase_for};
+use crate::modules::alerts::{active_alerts_at, get_alert_categories};
+use crate::modules::weather::{build_active_categories, get_weather_condition_category, get_weather_data};
+use crate::modules::location::resolve_location_query;
+use crate::modules::weather_cache::get_cached_weather_any_age;
+use crate::modules::settings::{load_app_settings, AppSettings};
+use crate::modules::scheduler::{get_wallpaper_path_for_category, select_category_for_conditions};
+use crate::modules::utils::get_location_key;
+
+const TIMELINE_HOURS: i64 = 24;
+
+fn hour_local_time(hour: &ForecastHour) -> Option<ChronoDateTime<Local>> {
+    ChronoDateTime::from_timestamp(hour.time_epoch, 0).map(|dt| dt.with_timezone(&Local))
+}
+
+// Sunrise/sunset for the day an hour falls on, falling back to today's astronomy data
+fn astro_for_hour<'a>(weather_data: &'a WeatherApiResponse, at: &ChronoDateTime<Local>) -> &'a AstroData {
+    let date = at.format("%Y-%m-%d").to_string();
+    weather_data
+        .forecast
+        .as_ref()
+        .and_then(|forecast| forecast.forecastday.iter().find(|day| day.date == date))
+        .map(|day| &day.astro)
+        .unwrap_or(&weather_data.astronomy.astro)
+}
+
+fn forecast_hours(weather_data: &WeatherApiResponse) -> Vec<&ForecastHour> {
+    weather_data
+        .forecast
+        .as_ref()
+        .map(|forecast| forecast.forecastday.iter().flat_map(|day| day.hour.iter()).collect())
+        .unwrap_or_default()
+}
+
+fn conditions_for_hour(
+    weather_data: &WeatherApiResponse,
+    hour: &ForecastHour,
+    settings: &AppSettings,
+) -> Option<(Option<String>, String, Vec<String>)> {
+    let at = hour_local_time(hour)?;
+    let astro = astro_for_hour(weather_data, &at);
+
+    let alerts = active_alerts_at(weather_data, hour.time_epoch);
+    let weather_condition = get_weather_condition_category(hour.condition.code, &hour.condition.text);
+    let time_period = get_time_period_at(at, Some(&astro.sunrise), Some(&astro.sunset));
+    // Pollen is only reported for the current hour
+    let mut environment_categories = get_air_quality_categories(hour.air_quality.as_ref(), None, settings);
+    environment_categories.extend(get_moon_categories(astro, &at, &time_period));
+    let active_categories = build_active_categories(
+        &get_alert_categories(&alerts),
+        &weather_condition,
+        &environment_categories,
+        &time_period,
+    );
+
+    Some((weather_condition, time_period, active_categories))
+}
+
+pub fn build_forecast_timeline(weather_data: &WeatherApiResponse) -> Vec<ForecastTimelineEntry> {
+    let settings = load_app_settings();
+    let now = Utc::now().timestamp();
+    let current_hour_start = now - now.rem_euclid(3600);
+    let end = current_hour_start + TIMELINE_HOURS * 3600;
+
+    let mut entries: Vec<ForecastTimelineEntry> = Vec::new();
+
+    for hour in forecast_hours(weather_data) {
+        if hour.time_epoch < current_hour_start || hour.time_epoch >= end {
+            continue;
+        }
+
+        let (weather_condition, time_period, active_categories) = match conditions_for_hour(weather_data, hour, &settings) {
+            Some(conditions) => conditions,
+            None => continue,
+        };
+
+        let wallpaper_category = select_category_for_conditions(&active_categories);
+        let wallpaper_path = wallpaper_category
+            .as_deref()
+            .and_then(get_wallpaper_path_for_category);
+        let is_change = entries
+            .last()
+            .map(|previous| previous.wallpaper_path != wallpaper_path)
+            .unwrap_or(false);
+
+        entries.push(ForecastTimelineEntry {
+            timestamp: hour.time_epoch,
+            time: hour_local_time(hour).map(|dt| dt.format("%H:%M").to_string()).unwrap_or_default(),
+            weather_condition,
+            time_period,
+            temperature: hour.temp_c,
+            chance_of_rain: hour.chance_of_rain,
+            chance_of_snow: hour.chance_of_snow,
+            active_categories,
+            wallpaper_category,
+            wallpaper_path,
+            is_change,
+        });
+    }
+
+    entries.sort_by_key(|entry| entry.timestamp);
+    entries
+}
+
+/// Builds current conditions from the forecast hour covering "now" in the last cached
+/// response, ignoring the cache TTL. Used by the scheduler when live data can't be fetched.
+pub fn get_forecast_conditions_for_now() -> Option<CurrentConditions> {
+    let settings = load_app_settings();
+    let location = resolve_location_query(&settings).ok()?;
+    let weather_data = get_cached_weather_any_age(&get_location_key(&location))?;
+
+    let now = Utc::now().timestamp();
+    let hour = forecast_hours(&weather_data)
+        .into_iter()
+        .find(|hour| now >= hour.time_epoch && now < hour.time_epoch + 3600)?;
+
+    let (weather_condition, time_period, active_categories) = conditions_for_hour(&weather_data, hour, &settings)?;
+    let at = hour_local_time(hour)?;
+    let astro = astro_for_hour(&weather_data, &at).clone();
+
+    Some(CurrentConditions {
+        weather_condition,
+        time_period,
+        temperature: Some(hour.temp_c),
+        humidity: Some(hour.humidity),
+        sunrise: Some(astro.sunrise.clone()),
+        sunset: Some(astro.sunset.clone()),
+        location: Some(format!("{}, {}", weather_data.location.name, weather_data.location.country)),
+        active_categories,
+        alerts: active_alerts_at(&weather_data, now),
+        air_quality_index: hour.air_quality.as_ref().and_then(|aq| aq.us_epa_index),
+        pm2_5: hour.air_quality.as_ref().and_then(|aq| aq.pm2_5),
+        pollen_count: None,
+        moon_phase: Some(moon_phase_for(&astro, &at)),
+        moon_illumination: Some(moon_illumination_for(&astro, &at)),
+    })
+}
+
+#[tauri::command]
+pub async fn get_forecast_timeline() -> Result<ForecastTimelineResponse, String> {
+    let weather_data = get_weather_data().await?;
+
+    if weather_data.forecast.is_none() {
+        return Err("Forecast data not available yet. Clear the weather cache to refresh.".to_string());
+    }
+
+    Ok(ForecastTimelineResponse {
+        location: Some(format!("{}, {}", weather_data.location.name, weather_data.location.country)),
+        entries: build_forecast_timeline(&weather_data),
+    })
+}
@@ -0,0 +1,200 @@
+use std::fs;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::modules::persistence::write_json_atomic;
+use crate::modules::settings::AppSettings;
+use crate::modules::utils::get_app_cache_dir;
+
+// Device location resolved natively (GeoClue on Linux), reused until the refresh interval elapses
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ResolvedLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub accuracy_meters: f64,
+    pub source: String, // "geoclue"
+    pub resolved_at: i64, // UTC timestamp
+}
+
+fn get_geolocation_cache_path() -> Result<std::path::PathBuf, String> {
+    let cache_dir = get_app_cache_dir()?;
+    Ok(cache_dir.join("geolocation_cache.json"))
+}
+
+pub fn load_resolved_location() -> Option<ResolvedLocation> {
+    let cache_path = get_geolocation_cache_path().ok()?;
+    let content = fs::read_to_string(cache_path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_resolved_location(location: &ResolvedLocation) -> Result<(), String> {
+    let cache_path = get_geolocation_cache_path()?;
+    write_json_atomic(&cache_path, location)
+        .map_err(|e| format!("Failed to write location cache: {}", e))
+}
+
+fn clear_resolved_location() {
+    if let Ok(cache_path) = get_geolocation_cache_path() {
+        let _ = fs::remove_file(cache_path);
+    }
+}
+
+fn uses_native_geolocation(settings: &AppSettings) -> bool {
+    settings.use_auto_location
+        && settings.native_geolocation_enabled
+        && settings.active_location_profile.is_none()
+}
+
+/// Coordinates to use for auto-location, if a native lookup has succeeded. None means
+/// callers should fall back to IP-based lookup.
+pub fn get_auto_location_coordinates(settings: &AppSettings) -> Option<(f64, f64)> {
+    if !uses_native_geolocation(settings) {
+        return None;
+    }
+    load_resolved_location().map(|location| (location.latitude, location.longitude))
+}
+
+/// Re-resolves the device location when the cached one is older than the configured
+/// refresh interval. If the native service is unavailable the cache is dropped so
+/// auto-location falls back to IP lookup.
+pub async fn refresh_auto_location(settings: &AppSettings) {
+    if !uses_native_geolocation(settings) {
+        return;
+    }
+
+    let refresh_seconds = (settings.geolocation_refresh_minutes * 60) as i64;
+    if let Some(cached) = load_resolved_location() {
+        if Utc::now().timestamp() - cached.resolved_at < refresh_seconds {
+            return;
+        }
+    }
+
+    match locate_device(&settings.geolocation_accuracy).await {
+        Ok(location) => {
+            if let Err(e) = save_resolved_location(&location) {
+                eprintln!("Warning: Failed to cache device location: {}", e);
+            }
+        }
+        Err(e) => {
+            eprintln!("Native geolocation unavailable, falling back to IP lookup: {}", e);
+            clear_resolved_location();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn locate_device(accuracy: &str) -> Result<ResolvedLocation, String> {
+    geoclue::locate(accuracy).await
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn locate_device(_accuracy: &str) -> Result<ResolvedLocation, String> {
+    Err("Native geolocation is only supported on Linux".to_string())
+}
+
+#[tauri::command]
+pub async fn get_resolved_location() -> Result<Option<ResolvedLocation>, String> {
+    Ok(load_resolved_location())
+}
+
+#[cfg(target_os = "linux")]
+mod geoclue {
+    use std::time::Duration;
+    use chrono::Utc;
+    use zbus::proxy::CacheProperties;
+    use zbus::zvariant::OwnedObjectPath;
+    use zbus::{Connection, Proxy};
+    use super::ResolvedLocation;
+
+    const SERVICE: &str = "org.freedesktop.GeoClue2";
+    const MANAGER_PATH: &str = "/org/freedesktop/GeoClue2/Manager";
+    const DESKTOP_ID: &str = "wallpaperthing";
+    const LOCATION_TIMEOUT: Duration = Duration::from_secs(15);
+
+    // GClueAccuracyLevel values
+    fn accuracy_level(accuracy: &str) -> u32 {
+        match accuracy {
+            "country" => 1,
+            "neighborhood" => 5,
+            "street" => 6,
+            "exact" => 8,
+            _ => 4, // city
+        }
+    }
+
+    fn dbus_error(context: &str) -> impl Fn(zbus::Error) -> String + '_ {
+        move |e| format!("GeoClue {}: {}", context, e)
+    }
+
+    pub async fn locate(accuracy: &str) -> Result<ResolvedLocation, String> {
+        let connection = Connection::system().await.map_err(dbus_error("connection failed"))?;
+
+        let manager = Proxy::new(&connection, SERVICE, MANAGER_PATH, "org.freedesktop.GeoClue2.Manager")
+            .await
+            .map_err(dbus_error("manager unavailable"))?;
+        let client_path: OwnedObjectPath = manager
+            .call("GetClient", &())
+            .await
+            .map_err(dbus_error("GetClient failed"))?;
+
+        // Property caching is disabled so polling "Location" sees updates as they arrive
+        let client: Proxy = zbus::proxy::Builder::new(&connection)
+            .destination(SERVICE)
+            .and_then(|builder| builder.path(client_path))
+            .and_then(|builder| builder.interface("org.freedesktop.GeoClue2.Client"))
+            .map_err(dbus_error("invalid client"))?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await
+            .map_err(dbus_error("client unavailable"))?;
+        client
+            .set_property("DesktopId", DESKTOP_ID)
+            .await
+            .map_err(|e| format!("GeoClue DesktopId rejected: {}", e))?;
+        client
+            .set_property("RequestedAccuracyLevel", accuracy_level(accuracy))
+            .await
+            .map_err(|e| format!("GeoClue accuracy rejected: {}", e))?;
+        client
+            .call::<_, _, ()>("Start", &())
+            .await
+            .map_err(dbus_error("Start failed"))?;
+
+        let result = tokio::time::timeout(LOCATION_TIMEOUT, wait_for_location(&connection, &client)).await;
+        let _ = client.call::<_, _, ()>("Stop", &()).await;
+
+        match result {
+            Ok(location) => location,
+            Err(_) => Err("GeoClue did not report a location in time".to_string()),
+        }
+    }
+
+    async fn wait_for_location(connection: &Connection, client: &Proxy<'_>) -> Result<ResolvedLocation, String> {
+        loop {
+            let location_path: OwnedObjectPath = client
+                .get_property("Location")
+                .await
+                .map_err(dbus_error("Location unavailable"))?;
+
+            // "/" until the first fix arrives
+            if location_path.as_str() != "/" {
+                let location = Proxy::new(connection, SERVICE, location_path, "org.freedesktop.GeoClue2.Location")
+                    .await
+                    .map_err(dbus_error("location unavailable"))?;
+
+                let latitude: f64 = location.get_property("Latitude").await.map_err(dbus_error("Latitude unavailable"))?;
+                let longitude: f64 = location.get_property("Longitude").await.map_err(dbus_error("Longitude unavailable"))?;
+                let accuracy_meters: f64 = location.get_property("Accuracy").await.unwrap_or(0.0);
+
+                return Ok(ResolvedLocation {
+                    latitude,
+                    longitude,
+                    accuracy_meters,
+                    source: "geoclue".to_string(),
+                    resolved_at: Utc::now().timestamp(),
+                });
+            }
+
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use crate::modules::geolocation::get_auto_location_coordinates;
+use crate::modules::secrets::redact_secrets;
+use crate::modules::settings::{load_app_settings, update_app_settings, AppSettings, LocationProfile};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GeocodingResult {
+    pub name: String,
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub country: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+pub fn get_active_profile(settings: &AppSettings) -> Option<&LocationProfile> {
+    let active_id = settings.active_location_profile.as_ref()?;
+    settings.location_profiles.iter().find(|profile| &profile.id == active_id)
+}
+
+/// Coordinates for the configured location: the active profile first, then the natively
+/// resolved device location (auto-location) or explicit lat/lon from settings. None when
+/// only a free-text location or IP-based auto-location is available.
+pub fn resolve_coordinates(settings: &AppSettings) -> Option<(f64, f64)> {
+    if let Some(profile) = get_active_profile(settings) {
+        return Some((profile.latitude, profile.longitude));
+    }
+
+    if settings.use_auto_location {
+        return get_auto_location_coordinates(settings);
+    }
+
+    match (settings.latitude, settings.longitude) {
+        (Some(lat), Some(lon)) => Some((lat, lon)),
+        _ => None,
+    }
+}
+
+fn format_coordinates(latitude: f64, longitude: f64) -> String {
+    format!("{:.4},{:.4}", latitude, longitude)
+}
+
+/// The `q` parameter sent to the weather provider.
+pub fn resolve_location_query(settings: &AppSettings) -> Result<String, String> {
+    if let Some((lat, lon)) = resolve_coordinates(settings) {
+        return Ok(format_coordinates(lat, lon));
+    }
+
+    if settings.use_auto_location {
+        Ok("auto:ip".to_string())
+    } else {
+        if settings.location.is_empty() {
+            return Err("Location not configured. Please set it in Settings or enable auto-location.".to_string());
+        }
+        Ok(settings.location.clone())
+    }
+}
+
+fn validate_coordinates(latitude: f64, longitude: f64) -> Result<(), String> {
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err(format!("Invalid coordinates: {}, {}", latitude, longitude));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn search_locations(query: String) -> Result<Vec<GeocodingResult>, String> {
+    let settings = load_app_settings();
+
+    if settings.weather_api_key.is_empty() {
+        return Err("Weather API key not configured. Please set it in Settings.".to_string());
+    }
+
+    if query.trim().len() < 3 {
+        return Ok(Vec::new());
+    }
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get("https://api.weatherapi.com/v1/search.json")
+        .query(&[("key", settings.weather_api_key.as_str()), ("q", query.trim())])
+        .send()
+        .await
+        .map_err(|e| redact_secrets(&format!("HTTP error: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Location search failed: {}", response.status()));
+    }
+
+    response
+        .json::<Vec<GeocodingResult>>()
+        .await
+        .map_err(|e| redact_secrets(&format!("Invalid search response: {}", e)))
+}
+
+#[tauri::command]
+pub async fn save_location_profile(profile: LocationProfile) -> Result<LocationProfile, String> {
+    validate_coordinates(profile.latitude, profile.longitude)?;
+
+    let mut profile = profile;
+    if profile.id.is_empty() {
+        profile.id = uuid::Uuid::new_v4().to_string();
+    }
+
+    update_app_settings(|settings| {
+        match settings.location_profiles.iter_mut().find(|p| p.id == profile.id) {
+            Some(existing) => *existing = profile.clone(),
+            None => settings.location_profiles.push(profile.clone()),
+        }
+        Ok(())
+    })?;
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn delete_location_profile(id: String) -> Result<String, String> {
+    update_app_settings(|settings| {
+        settings.location_profiles.retain(|profile| profile.id != id);
+
+        if settings.active_location_profile.as_ref() == Some(&id) {
+            settings.active_location_profile = None;
+        }
+        Ok(())
+    })?;
+    Ok("Location profile deleted".to_string())
+}
+
+/// Switches to a saved profile, or back to the regular location settings when `id` is None.
+pub fn set_active_location_profile(id: Option<String>) -> Result<AppSettings, String> {
+    update_app_settings(|settings| {
+        if let Some(ref id) = id {
+            if !settings.location_profiles.iter().any(|profile| &profile.id == id) {
+                return Err(format!("Location profile not found: {}", id));
+            }
+        }
+
+        settings.active_location_profile = id;
+        Ok(settings.clone())
+    })
+}
+
+#[tauri::command]
+pub async fn set_active_location_profile_cmd(id: Option<String>) -> Result<String, String> {
+    let settings = set_active_location_profile(id)?;
+
+    match get_active_profile(&settings) {
+        Some(profile) => Ok(format!("Switched location to {}", profile.name)),
+        None => Ok("Switched location to default settings".to_string()),
+    }
+}
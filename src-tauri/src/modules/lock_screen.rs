@@ -0,0 +1,181 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::modules::persistence::replace_file;
+use crate::modules::wallpaper_backend::{active_backend, has_executable, is_wayland};
+
+const SWAYLOCK_CONFIG: &str = "swaylock/config";
+const LIGHTDM_GTK_GREETER_CONFIG: &str = "/etc/lightdm/lightdm-gtk-greeter.conf";
+
+/// Sets `key` to `value` in an INI-style file, inside `section` if given, keeping the rest
+/// of the file as it is. The key is appended if it isn't there yet.
+fn set_ini_value(content: &str, section: Option<&str>, key: &str, value: &str) -> String {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let entry = format!("{}={}", key, value);
+
+    // swaylock's config has no sections, so the whole file counts as the target
+    let mut in_section = section.is_none();
+    let mut section_end = if in_section { Some(lines.len()) } else { None };
+
+    for index in 0..lines.len() {
+        let line = lines[index].trim();
+        if line.starts_with('[') && line.ends_with(']') {
+            if in_section && section.is_some() {
+                break;
+            }
+            in_section = section == Some(&line[1..line.len() - 1]);
+            if in_section {
+                section_end = Some(index + 1);
+            }
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        if line.split('=').next().map(str::trim) == Some(key) {
+            lines[index] = entry;
+            return lines.join("\n") + "\n";
+        }
+        if section.is_some() && !line.is_empty() {
+            section_end = Some(index + 1);
+        }
+    }
+
+    match (section, section_end) {
+        (_, Some(index)) => lines.insert(index, entry),
+        (Some(section), None) => {
+            lines.push(format!("[{}]", section));
+            lines.push(entry);
+        }
+        (None, None) => lines.push(entry),
+    }
+    lines.join("\n") + "\n"
+}
+
+// These files belong to other programs, so they're replaced without the persistence
+// layer's lock, which would leave a lock file next to them. The replacement keeps the
+// original's permissions, and a symlinked config (e.g. from a dotfiles repo) is updated
+// where it points rather than replaced by a plain file.
+fn update_config_file(path: &Path, section: Option<&str>, key: &str, value: &str) -> Result<(), String> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let content = fs::read_to_string(&path).unwrap_or_default();
+    let updated = set_ini_value(&content, section, key, value);
+    if updated == content {
+        return Ok(());
+    }
+
+    let permissions = fs::metadata(&path).ok().map(|metadata| metadata.permissions());
+    replace_file(&path, updated.as_bytes(), permissions)
+}
+
+fn swaylock_config_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join(SWAYLOCK_CONFIG))
+}
+
+fn set_swaylock_image(path: &Path) -> Result<(), String> {
+    let config_path = swaylock_config_path().ok_or("Could not find config directory")?;
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create swaylock config directory: {}", e))?;
+    }
+    update_config_file(&config_path, None, "image", &path.to_string_lossy())
+}
+
+// The greeter config is system-wide and usually only writable by root. The greeter also
+// runs as its own user, so the image has to be readable outside the home directory.
+fn set_lightdm_greeter_background(path: &Path) -> Result<(), String> {
+    update_config_file(Path::new(LIGHTDM_GTK_GREETER_CONFIG), Some("greeter"), "background", &path.to_string_lossy())
+}
+
+fn lightdm_greeter_writable() -> bool {
+    fs::OpenOptions::new().append(true).open(LIGHTDM_GTK_GREETER_CONFIG).is_ok()
+}
+
+/// Sets the lock screen image everywhere the session can use it: through the wallpaper
+/// backend (GNOME, KDE), swaylock's config and the LightDM GTK greeter where writable.
+/// Returns the targets that were updated.
+pub fn apply_lock_screen(path: &Path) -> Result<Vec<String>, String> {
+    let mut applied = Vec::new();
+    let mut errors = Vec::new();
+
+    let backend = active_backend();
+    if backend.supports_lock_screen() {
+        match backend.set_lock_screen(path) {
+            Ok(()) => applied.push(backend.name().to_string()),
+            Err(e) => errors.push(format!("{}: {}", backend.name(), e)),
+        }
+    }
+
+    if is_wayland() && has_executable("swaylock") {
+        match set_swaylock_image(path) {
+            Ok(()) => applied.push("swaylock".to_string()),
+            Err(e) => errors.push(format!("swaylock: {}", e)),
+        }
+    }
+
+    if lightdm_greeter_writable() {
+        match set_lightdm_greeter_background(path) {
+            Ok(()) => applied.push("LightDM greeter".to_string()),
+            Err(e) => errors.push(format!("LightDM greeter: {}", e)),
+        }
+    }
+
+    if applied.is_empty() {
+        if errors.is_empty() {
+            return Err("No supported lock screen found".to_string());
+        }
+        return Err(errors.join("; "));
+    }
+    for error in errors {
+        eprintln!("Warning: Failed to set lock screen image for {}", error);
+    }
+    Ok(applied)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    #[test]
+    fn update_keeps_permissions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lightdm-gtk-greeter.conf");
+        fs::write(&path, "[greeter]\ntheme-name=Adwaita\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        update_config_file(&path, Some("greeter"), "background", "/tmp/lock.png").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[greeter]\ntheme-name=Adwaita\nbackground=/tmp/lock.png\n");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+    }
+
+    #[test]
+    fn unchanged_value_is_not_rewritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config");
+        fs::write(&path, "color=000000\nimage=/tmp/lock.png\n").unwrap();
+        let inode = fs::metadata(&path).unwrap().ino();
+
+        update_config_file(&path, None, "image", "/tmp/lock.png").unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().ino(), inode);
+    }
+
+    #[test]
+    fn symlinked_config_is_updated_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("dotfiles-swaylock");
+        let link = dir.path().join("config");
+        fs::write(&target, "color=000000\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        update_config_file(&link, None, "image", "/tmp/lock.png").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "color=000000\nimage=/tmp/lock.png\n");
+    }
+
+    #[test]
+    fn missing_config_is_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config");
+        update_config_file(&path, None, "image", "/tmp/lock.png").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "image=/tmp/lock.png\n");
+    }
+}
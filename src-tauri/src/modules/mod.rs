@@ -2,8 +2,31 @@ pub mod types;
 pub mod utils;
 pub mod time;
 pub mod weather;
+pub mod weather_cache;
+pub mod forecast;
+pub mod alerts;
+pub mod air_quality;
+pub mod moon;
+pub mod location;
+pub mod geolocation;
+pub mod sun;
+pub mod tray;
+pub mod notifications;
 pub mod wallpaper;
 pub mod greet;
 pub mod scheduler;
 pub mod settings;
-pub mod backup;
\ No newline at end of file
+pub mod backup;
+pub mod backup_integrity;
+pub mod backup_restore;
+pub mod backup_merge;
+pub mod backup_crypto;
+pub mod auto_backup;
+pub mod secrets;
+pub mod config_migration;
+pub mod persistence;
+pub mod config_watcher;
+pub mod declarative_config;
+pub mod wallpaper_backend;
+pub mod wallpaper_transition;
+pub mod lock_screen;
\ No newline at end of file
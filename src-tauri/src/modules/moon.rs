@@ -0,0 +1,92 @@
+use std::f64::consts::PI;
+use chrono::{DateTime as ChronoDateTime, Local};
+use crate::modules::types::AstroData;
+use crate::modules::time::parse_time_to_timestamp_on;
+
+const SYNODIC_MONTH_DAYS: f64 = 29.530588853;
+const REFERENCE_NEW_MOON: i64 = 947182440; // 2000-01-06 18:14 UTC
+
+// Moon categories only make sense once it's dark
+const NIGHT_PERIODS: [&str; 3] = ["dusk", "night", "late_night"];
+
+/// Fraction of the lunar cycle elapsed at the given UTC timestamp (0.0 = new moon, 0.5 = full moon).
+fn lunar_cycle_fraction(timestamp: i64) -> f64 {
+    let days_since_reference = (timestamp - REFERENCE_NEW_MOON) as f64 / 86400.0;
+    days_since_reference.rem_euclid(SYNODIC_MONTH_DAYS) / SYNODIC_MONTH_DAYS
+}
+
+/// Local approximation of the moon phase, used when the provider doesn't report one.
+/// Names match the ones WeatherAPI returns.
+pub fn local_moon_phase(timestamp: i64) -> String {
+    let fraction = lunar_cycle_fraction(timestamp);
+    let phase = match fraction {
+        f if f < 0.0339 => "New Moon",
+        f if f < 0.2161 => "Waxing Crescent",
+        f if f < 0.2839 => "First Quarter",
+        f if f < 0.4661 => "Waxing Gibbous",
+        f if f < 0.5339 => "Full Moon",
+        f if f < 0.7161 => "Waning Gibbous",
+        f if f < 0.7839 => "Last Quarter",
+        f if f < 0.9661 => "Waning Crescent",
+        _ => "New Moon",
+    };
+    phase.to_string()
+}
+
+pub fn local_moon_illumination(timestamp: i64) -> f32 {
+    let fraction = lunar_cycle_fraction(timestamp);
+    ((1.0 - (2.0 * PI * fraction).cos()) / 2.0 * 100.0) as f32
+}
+
+pub fn moon_phase_for(astro: &AstroData, at: &ChronoDateTime<Local>) -> String {
+    astro
+        .moon_phase
+        .clone()
+        .filter(|phase| !phase.is_empty())
+        .unwrap_or_else(|| local_moon_phase(at.timestamp()))
+}
+
+pub fn moon_illumination_for(astro: &AstroData, at: &ChronoDateTime<Local>) -> f32 {
+    astro
+        .moon_illumination
+        .unwrap_or_else(|| local_moon_illumination(at.timestamp()))
+}
+
+fn is_moon_up(astro: &AstroData, at: &ChronoDateTime<Local>) -> bool {
+    let date = at.date_naive();
+    // "No moonrise" / "No moonset" fail to parse and are treated as missing
+    let moonrise = astro.moonrise.as_deref().and_then(|t| parse_time_to_timestamp_on(t, date));
+    let moonset = astro.moonset.as_deref().and_then(|t| parse_time_to_timestamp_on(t, date));
+    let t = at.timestamp();
+
+    match (moonrise, moonset) {
+        (Some(rise), Some(set)) if rise < set => t >= rise && t < set,
+        // Moon set earlier in the day and rises again later, so it's up across midnight
+        (Some(rise), Some(set)) => t >= rise || t < set,
+        (Some(rise), None) => t >= rise,
+        (None, Some(set)) => t < set,
+        (None, None) => false,
+    }
+}
+
+/// Categories for the moon at the given moment: "full_moon" / "new_moon" from the phase
+/// and "moon_up" while the moon is above the horizon. Only produced at night.
+pub fn get_moon_categories(astro: &AstroData, at: &ChronoDateTime<Local>, time_period: &str) -> Vec<String> {
+    let mut categories = Vec::new();
+
+    if !NIGHT_PERIODS.contains(&time_period) {
+        return categories;
+    }
+
+    match moon_phase_for(astro, at).as_str() {
+        "Full Moon" => categories.push("full_moon".to_string()),
+        "New Moon" => categories.push("new_moon".to_string()),
+        _ => {}
+    }
+
+    if is_moon_up(astro, at) {
+        categories.push("moon_up".to_string());
+    }
+
+    categories
+}
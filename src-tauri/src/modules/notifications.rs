@@ -0,0 +1,26 @@
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+// Handle captured during setup so background tasks (scheduler, alerts) can reach the app
+static APP_HANDLE: Mutex<Option<AppHandle>> = Mutex::new(None);
+
+pub fn set_app_handle(app_handle: AppHandle) {
+    let mut handle = APP_HANDLE.lock().unwrap();
+    *handle = Some(app_handle);
+}
+
+pub fn get_app_handle() -> Option<AppHandle> {
+    APP_HANDLE.lock().unwrap().clone()
+}
+
+pub fn send_notification(title: &str, body: &str) {
+    let app_handle = match get_app_handle() {
+        Some(handle) => handle,
+        None => return,
+    };
+
+    if let Err(e) = app_handle.notification().builder().title(title).body(body).show() {
+        eprintln!("Failed to show notification: {}", e);
+    }
+}
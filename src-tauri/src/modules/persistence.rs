@@ -0,0 +1,233 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::Serialize;
+
+// Distinguishes temp files from concurrent writers within this process
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Exclusive lock on a config file, held on a "<file>.lock" sidecar so the file itself
+/// can be replaced by rename. Released when dropped.
+pub struct FileLock {
+    path: PathBuf,
+    _lock_file: File,
+}
+
+fn sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(format!("{}{}", name, suffix))
+}
+
+/// Blocks until this process holds the lock for `path`. Other threads and processes
+/// taking the same lock wait, so load-modify-save sequences don't interleave.
+pub fn lock_file(path: &Path) -> Result<FileLock, String> {
+    let lock_path = sidecar_path(path, ".lock");
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| format!("Failed to open lock file {}: {}", lock_path.display(), e))?;
+
+    lock_file
+        .lock()
+        .map_err(|e| format!("Failed to lock {}: {}", path.display(), e))?;
+
+    Ok(FileLock {
+        path: path.to_path_buf(),
+        _lock_file: lock_file,
+    })
+}
+
+impl FileLock {
+    /// Replaces the locked file's contents atomically.
+    pub fn write(&self, contents: &[u8]) -> Result<(), String> {
+        replace_file(&self.path, contents, None)
+    }
+
+    /// Like `write`, for files only the owner may read. The contents are never in a file
+    /// with wider permissions, not even the temp file.
+    pub fn write_private(&self, contents: &[u8]) -> Result<(), String> {
+        replace_file(&self.path, contents, owner_only_permissions())
+    }
+
+    pub fn write_json<T: Serialize>(&self, value: &T) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(value)
+            .map_err(|e| format!("Failed to serialize {}: {}", self.path.display(), e))?;
+        self.write(content.as_bytes())
+    }
+}
+
+/// Serializes `value` as pretty JSON and atomically replaces `path` with it.
+pub fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    lock_file(path)?.write_json(value)
+}
+
+/// Writes to a temp file in the same directory, flushes it to disk and renames it over
+/// `path`, so readers and a crash at any point only ever see the old or the new contents.
+/// The new file gets `permissions` if given. Callers sharing the file with other writers
+/// should hold its `lock_file` lock.
+pub fn replace_file(path: &Path, contents: &[u8], permissions: Option<fs::Permissions>) -> Result<(), String> {
+    let temp_path = sidecar_path(
+        path,
+        &format!(".tmp-{}-{}", std::process::id(), TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)),
+    );
+
+    let written = create_temp_file(&temp_path, permissions)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("Failed to write {}: {}", path.display(), e));
+    }
+
+    sync_parent_dir(path);
+    Ok(())
+}
+
+fn create_temp_file(temp_path: &Path, permissions: Option<fs::Permissions>) -> io::Result<File> {
+    // The name is unique to this process, so anything already there was left by a crashed
+    // process with the same pid. Never writing through an existing file also means never
+    // following a symlink planted in its place.
+    let _ = fs::remove_file(temp_path);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if let Some(permissions) = &permissions {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(permissions.mode() & 0o777);
+    }
+
+    let file = options.open(temp_path)?;
+    // The mode above is reduced by the umask; set exactly what was asked for
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+    Ok(file)
+}
+
+#[cfg(unix)]
+fn owner_only_permissions() -> Option<fs::Permissions> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn owner_only_permissions() -> Option<fs::Permissions> {
+    None
+}
+
+// Makes the rename itself durable
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) {
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use serde_json::{json, Value};
+
+    fn read_json(path: &Path) -> Value {
+        let content = fs::read_to_string(path).unwrap();
+        serde_json::from_str(&content).unwrap_or_else(|e| panic!("invalid JSON {:?}: {}", content, e))
+    }
+
+    #[test]
+    fn concurrent_read_modify_write_loses_no_updates() {
+        const THREADS: usize = 8;
+        const INCREMENTS: u64 = 25;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter.json");
+        write_json_atomic(&path, &json!({ "count": 0, "writers": [] })).unwrap();
+
+        let workers: Vec<_> = (0..THREADS)
+            .map(|id| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    for _ in 0..INCREMENTS {
+                        let lock = lock_file(&path).unwrap();
+                        let mut document = read_json(&path);
+                        document["count"] = json!(document["count"].as_u64().unwrap() + 1);
+                        document["writers"].as_array_mut().unwrap().push(json!(id));
+                        lock.write_json(&document).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        // Readers that don't take the lock still only ever see a complete document
+        while workers.iter().any(|worker| !worker.is_finished()) {
+            read_json(&path);
+        }
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let document = read_json(&path);
+        assert_eq!(document["count"], json!(THREADS as u64 * INCREMENTS));
+        assert_eq!(document["writers"].as_array().unwrap().len(), THREADS * INCREMENTS as usize);
+    }
+
+    #[test]
+    fn interrupted_write_leaves_target_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        write_json_atomic(&path, &json!({ "theme": "dark" })).unwrap();
+
+        // What a crash between writing the temp file and renaming it leaves behind
+        let leftover = sidecar_path(&path, ".tmp-4242-0");
+        fs::write(&leftover, b"{\"theme\": \"li").unwrap();
+
+        assert_eq!(read_json(&path), json!({ "theme": "dark" }));
+
+        write_json_atomic(&path, &json!({ "theme": "light" })).unwrap();
+        assert_eq!(read_json(&path), json!({ "theme": "light" }));
+        assert_eq!(fs::read(&leftover).unwrap(), b"{\"theme\": \"li");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_write_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret");
+        fs::write(&path, b"old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        lock_file(&path).unwrap().write_private(b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn failed_write_removes_its_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        // Renaming a file over a non-empty directory fails after the temp file was written
+        let path = dir.path().join("blocked");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("inside"), b"").unwrap();
+
+        assert!(lock_file(&path).unwrap().write(b"{}").is_err());
+        let names: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert!(names.iter().all(|name| !name.contains(".tmp-")), "temp file left behind: {:?}", names);
+    }
+}
@@ -1,37 +1,77 @@
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::time::Duration;
 use tokio::time::interval;
 use crate::modules::weather::get_current_conditions;
+use crate::modules::forecast::get_forecast_conditions_for_now;
+use crate::modules::alerts::notify_alert_transitions;
+use crate::modules::sun::get_offline_conditions;
 use crate::modules::wallpaper::set_wallpaper;
-use crate::modules::utils::get_app_data_dir;
-use std::fs;
+use crate::modules::lock_screen::apply_lock_screen;
+use crate::modules::utils::get_app_config_dir;
+use crate::modules::auto_backup;
+use crate::modules::config_migration::{insert_missing_fields, load_versioned, Migration};
+use crate::modules::declarative_config::{apply_declared_collections, declared_scheduler, keep_file_values, managed_scheduler_fields};
+use crate::modules::persistence::{lock_file, FileLock};
+use crate::modules::notifications::get_app_handle;
+use crate::modules::wallpaper_backend::active_backend;
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
+use tauri::Emitter;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 struct SchedulerConfig {
+    #[serde(default)]
+    schema_version: u32,
     enabled: bool,
     interval_minutes: u64,
     last_applied_path: Option<String>,
+    #[serde(default = "default_external_change_action")]
+    external_change_action: String, // "pause" or "reapply" when the wallpaper is changed outside the app
+}
+
+fn default_external_change_action() -> String {
+    "pause".to_string()
+}
+
+// Set when someone else changed the wallpaper; automation stays paused until resumed
+#[derive(Serialize, Clone, Debug)]
+pub struct ManualOverride {
+    pub detected_at: String,
+    pub wallpaper: Option<String>, // What's on screen instead, if the backend can tell
 }
 
 impl Default for SchedulerConfig {
     fn default() -> Self {
         Self {
+            schema_version: SCHEDULER_CONFIG_SCHEMA_VERSION,
             enabled: false,
             interval_minutes: 3,
             last_applied_path: None,
+            external_change_action: default_external_change_action(),
         }
     }
 }
 
+const SCHEDULER_CONFIG_SCHEMA_VERSION: u32 = 1;
+
+const SCHEDULER_CONFIG_MIGRATIONS: [Migration; 1] = [
+    // 0 -> 1: unversioned files may predate interval_minutes
+    |config| insert_missing_fields(config, serde_json::json!({
+        "enabled": false,
+        "interval_minutes": 3,
+    })),
+];
+
 static SCHEDULER_CONFIG: Mutex<Option<SchedulerConfig>> = Mutex::new(None);
 static SCHEDULER_HANDLE: Mutex<Option<tokio::task::JoinHandle<()>>> = Mutex::new(None);
 static COLLECTION_DATA: Mutex<Option<Value>> = Mutex::new(None);
+static MANUAL_OVERRIDE: Mutex<Option<ManualOverride>> = Mutex::new(None);
 
 fn get_scheduler_config_path() -> Result<std::path::PathBuf, String> {
-    let app_dir = get_app_data_dir()?;
-    Ok(app_dir.join("scheduler_config.json"))
+    let config_dir = get_app_config_dir()?;
+    Ok(config_dir.join("scheduler_config.json"))
 }
 
 fn load_scheduler_config() -> SchedulerConfig {
@@ -40,22 +80,63 @@ fn load_scheduler_config() -> SchedulerConfig {
         Err(_) => return SchedulerConfig::default(),
     };
 
-    if let Ok(content) = fs::read_to_string(config_path) {
-        serde_json::from_str(&content).unwrap_or_default()
-    } else {
-        SchedulerConfig::default()
+    let (config, needs_save) = read_scheduler_config(&config_path);
+    if needs_save {
+        if let Err(e) = save_scheduler_config(&config) {
+            eprintln!("Warning: Failed to save migrated scheduler config: {}", e);
+        }
+    }
+    config
+}
+
+fn read_scheduler_config(config_path: &Path) -> (SchedulerConfig, bool) {
+    let loaded = load_versioned::<SchedulerConfig>(config_path, SCHEDULER_CONFIG_SCHEMA_VERSION, &SCHEDULER_CONFIG_MIGRATIONS);
+    let mut config = loaded.value;
+    apply_declared_scheduler(&mut config);
+    (config, loaded.needs_save)
+}
+
+// Values declared in config.toml win over the saved ones
+fn apply_declared_scheduler(config: &mut SchedulerConfig) {
+    let declared = declared_scheduler();
+    if let Some(enabled) = declared.enabled {
+        config.enabled = enabled;
+    }
+    if let Some(interval_minutes) = declared.interval_minutes {
+        config.interval_minutes = interval_minutes;
     }
 }
 
 fn save_scheduler_config(config: &SchedulerConfig) -> Result<(), String> {
     let config_path = get_scheduler_config_path()?;
-    let content = serde_json::to_string_pretty(config)
+    write_scheduler_config(&lock_file(&config_path)?, &config_path, config)
+}
+
+// Loads the config, applies `update` and saves it under the file's lock, so concurrent
+// updates (a tick recording the applied wallpaper, a command from the UI) don't lose each
+// other's changes. Returns the saved config.
+fn update_scheduler_config(update: impl FnOnce(&mut SchedulerConfig) -> Result<(), String>) -> Result<SchedulerConfig, String> {
+    let config_path = get_scheduler_config_path()?;
+    let config_lock = lock_file(&config_path)?;
+
+    let (mut config, _) = read_scheduler_config(&config_path);
+    update(&mut config)?;
+    write_scheduler_config(&config_lock, &config_path, &config)?;
+    Ok(config)
+}
+
+fn write_scheduler_config(config_lock: &FileLock, config_path: &Path, config: &SchedulerConfig) -> Result<(), String> {
+    let config = SchedulerConfig {
+        schema_version: SCHEDULER_CONFIG_SCHEMA_VERSION,
+        ..config.clone()
+    };
+    // Fields declared in config.toml keep their previous values in the JSON file
+    let mut document = serde_json::to_value(&config)
         .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    keep_file_values(&mut document, config_path, &managed_scheduler_fields());
     
-    fs::write(config_path, content)
-        .map_err(|e| format!("Failed to write config: {}", e))?;
-    
-    Ok(())
+    config_lock.write_json(&document)
+        .map_err(|e| format!("Failed to write config: {}", e))
 }
 
 async fn scheduler_loop(config: SchedulerConfig) {
@@ -89,13 +170,14 @@ async fn scheduler_loop(config: SchedulerConfig) {
             Ok(applied) => {
                 if applied {
                     // Update the global config with new last_applied_path
-                    {
-                        let mut global_config = SCHEDULER_CONFIG.lock().unwrap();
-                        if let Some(ref mut global) = *global_config {
-                            global.last_applied_path = mutable_config.last_applied_path.clone();
-                            let _ = save_scheduler_config(global);
-                        }
+                    let last_applied_path = mutable_config.last_applied_path.clone();
+                    if let Some(ref mut global) = *SCHEDULER_CONFIG.lock().unwrap() {
+                        global.last_applied_path = last_applied_path.clone();
                     }
+                    let _ = update_scheduler_config(|config| {
+                        config.last_applied_path = last_applied_path;
+                        Ok(())
+                    });
                 }
             }
             Err(e) => {
@@ -107,31 +189,90 @@ async fn scheduler_loop(config: SchedulerConfig) {
     println!("Wallpaper scheduler loop ended");
 }
 
+fn is_same_wallpaper(expected: &Path, actual: &Path) -> bool {
+    // Backends may report symlinks resolved or not
+    match (fs::canonicalize(expected), fs::canonicalize(actual)) {
+        (Ok(expected), Ok(actual)) => expected == actual,
+        _ => expected == actual,
+    }
+}
+
+/// Asks the backend what's on screen and compares it with what the scheduler applied last.
+/// Returns the wallpaper found instead, or `None` if nothing changed or the backend can't tell.
+async fn detect_external_change(expected: &str) -> Option<PathBuf> {
+    let expected = PathBuf::from(expected);
+    tokio::task::spawn_blocking(move || {
+        let actual = active_backend().current().filter(|actual| !actual.as_os_str().is_empty())?;
+        (!is_same_wallpaper(&expected, &actual)).then_some(actual)
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+// Reacts to a wallpaper set outside the app, following the external change action.
+// Returns whether automation is now paused.
+fn pause_for_external_change(config: &mut SchedulerConfig, actual: &Path) -> bool {
+    println!("Wallpaper changed outside the app: {}", actual.display());
+    if config.external_change_action == "reapply" {
+        config.last_applied_path = None;
+        return false;
+    }
+
+    let manual_override = ManualOverride {
+        detected_at: chrono::Utc::now().to_rfc3339(),
+        wallpaper: Some(actual.to_string_lossy().to_string()),
+    };
+    *MANUAL_OVERRIDE.lock().unwrap() = Some(manual_override.clone());
+    if let Some(app_handle) = get_app_handle() {
+        let _ = app_handle.emit("manual-override-detected", manual_override);
+    }
+    true
+}
+
+// Starting by hand ends a pause after a manual wallpaper change. The applied wallpaper is
+// forgotten too, or the first check would find the manual change again and pause anew.
+fn end_manual_override(config: &mut SchedulerConfig) {
+    *MANUAL_OVERRIDE.lock().unwrap() = None;
+    config.last_applied_path = None;
+}
+
 async fn check_and_apply_wallpaper(config: &mut SchedulerConfig) -> Result<bool, String> {
-    // Get current conditions
-    let conditions = get_current_conditions().await?;
-    
-    // Find the highest priority wallpaper for current conditions
-    let mut best_category: Option<String> = None;
-    let mut best_priority = -1i32;
-    
-    // Check all active categories and find the one with highest priority that has a configured wallpaper
-    for category in &conditions.active_categories {
-        if get_wallpaper_path_for_category(category).is_some() {
-            let priority = get_category_priority_from_settings(category).unwrap_or(get_category_priority(category));
-            if priority > best_priority {
-                best_priority = priority;
-                best_category = Some(category.clone());
-            }
-        }
+    if MANUAL_OVERRIDE.lock().unwrap().is_some() {
+        println!("Wallpaper was changed manually, automation paused");
+        return Ok(false);
     }
-    
-    // If no active category has a wallpaper, fall back to "default"
-    if best_category.is_none() {
-        if get_wallpaper_path_for_category("default").is_some() {
-            best_category = Some("default".to_string());
+
+    let external_change = match config.last_applied_path.clone() {
+        Some(expected) => detect_external_change(&expected).await,
+        None => None,
+    };
+    if let Some(actual) = external_change {
+        if pause_for_external_change(config, &actual) {
+            return Ok(false);
         }
     }
+
+    // Get current conditions, falling back to the last known forecast and then to
+    // sun/moon calculations from the configured coordinates when the API is unreachable
+    let conditions = match get_current_conditions().await {
+        Ok(conditions) => conditions,
+        Err(e) => {
+            if let Some(conditions) = get_forecast_conditions_for_now() {
+                println!("Weather unavailable ({}), using cached forecast instead", e);
+                conditions
+            } else if let Some(conditions) = get_offline_conditions() {
+                println!("Weather unavailable ({}), using local sun calculations instead", e);
+                conditions
+            } else {
+                return Err(e);
+            }
+        }
+    };
+    
+    notify_alert_transitions(&conditions.alerts);
+    
+    let best_category = select_category_for_conditions(&conditions.active_categories);
     
     if let Some(category) = best_category {
         if let Some(wallpaper_path) = get_wallpaper_path_for_category(&category) {
@@ -141,6 +282,9 @@ async fn check_and_apply_wallpaper(config: &mut SchedulerConfig) -> Result<bool,
                 
                 match set_wallpaper(wallpaper_path.clone()).await {
                     Ok(_) => {
+                        if lock_screen_enabled_for_category(&category) {
+                            apply_category_lock_screen(&category, &wallpaper_path).await;
+                        }
                         config.last_applied_path = Some(wallpaper_path);
                         println!("Successfully applied {} wallpaper", category);
                         return Ok(true);
@@ -161,6 +305,33 @@ async fn check_and_apply_wallpaper(config: &mut SchedulerConfig) -> Result<bool,
     Ok(false)
 }
 
+/// Picks the highest priority active category that has a wallpaper configured in the
+/// active collection, falling back to "default".
+pub fn select_category_for_conditions(active_categories: &[String]) -> Option<String> {
+    let mut best_category: Option<String> = None;
+    let mut best_priority = -1i32;
+    
+    // Check all active categories and find the one with highest priority that has a configured wallpaper
+    for category in active_categories {
+        if get_wallpaper_path_for_category(category).is_some() {
+            let priority = get_category_priority_from_settings(category).unwrap_or(get_category_priority(category));
+            if priority > best_priority {
+                best_priority = priority;
+                best_category = Some(category.clone());
+            }
+        }
+    }
+    
+    // If no active category has a wallpaper, fall back to "default"
+    if best_category.is_none() {
+        if get_wallpaper_path_for_category("default").is_some() {
+            best_category = Some("default".to_string());
+        }
+    }
+    
+    best_category
+}
+
 fn get_category_priority_from_settings(category: &str) -> Option<i32> {
     let collection_data = COLLECTION_DATA.lock().unwrap();
     
@@ -187,15 +358,26 @@ fn get_category_priority_from_settings(category: &str) -> Option<i32> {
 
 fn get_category_priority(category: &str) -> i32 {
     match category {
+        // Alerts outrank every other category
+        "alert_extreme" => 140,
+        "alert_severe" => 130,
+        "alert_moderate" => 120,
+        "alert_minor" => 110,
+        "alert" => 105,
         "thunderstorm" => 100,
         "rain" => 90,
         "snow" => 85,
+        "smog" => 82,
         "fog" => 80,
         "dawn" => 75,
         "dusk" => 70,
+        "full_moon" => 68,
+        "new_moon" => 67,
+        "moon_up" => 66,
         "late_night" => 65,
         "night" => 60,
         "evening" => 55,
+        "high_pollen" => 50,
         "cloudy" => 45,
         "sunny" => 40,
         "afternoon" => 35,
@@ -206,7 +388,7 @@ fn get_category_priority(category: &str) -> i32 {
     }
 }
 
-fn get_wallpaper_path_for_category(category: &str) -> Option<String> {
+pub fn get_wallpaper_path_for_category(category: &str) -> Option<String> {
     let collection_data = COLLECTION_DATA.lock().unwrap();
     
     if let Some(store_data) = collection_data.as_ref() {
@@ -235,43 +417,143 @@ fn get_wallpaper_path_for_category(category: &str) -> Option<String> {
     None
 }
 
-#[tauri::command]
-pub async fn start_wallpaper_scheduler(interval_minutes: Option<u64>) -> Result<String, String> {
-    let interval = interval_minutes.unwrap_or(3);
-    
-    // Stop existing scheduler if running
-    stop_wallpaper_scheduler().await?;
-    
-    let mut config = load_scheduler_config();
-    config.enabled = true;
-    config.interval_minutes = interval;
-    
-    // Save config
-    save_scheduler_config(&config)?;
-    
-    // Update global config
+/// Whether the active collection's setting for the category also sets the lock screen.
+pub fn lock_screen_enabled_for_category(category: &str) -> bool {
+    let collection_data = COLLECTION_DATA.lock().unwrap();
+    let store_data = match collection_data.as_ref() {
+        Some(store_data) => store_data,
+        None => return false,
+    };
+
+    store_data
+        .get("activeCollectionId")
+        .and_then(|id| id.as_str())
+        .and_then(|id| store_data.get("collections")?.get(id))
+        .and_then(|collection| collection.get("settings")?.get(category)?.get("lockScreen")?.as_bool())
+        .unwrap_or(false)
+}
+
+// The lock screen is a nice-to-have, so failing to set it doesn't fail the wallpaper change
+async fn apply_category_lock_screen(category: &str, wallpaper_path: &str) {
+    let path = PathBuf::from(wallpaper_path);
+    match tokio::task::spawn_blocking(move || apply_lock_screen(&path)).await {
+        Ok(Ok(targets)) => println!("Set {} lock screen for {}", category, targets.join(", ")),
+        Ok(Err(e)) => eprintln!("Failed to set lock screen: {}", e),
+        Err(e) => eprintln!("Failed to set lock screen: {}", e),
+    }
+}
+
+// Publishes the config and starts the loop, replacing any loop already running
+fn spawn_scheduler(config: SchedulerConfig) {
     {
         let mut global_config = SCHEDULER_CONFIG.lock().unwrap();
         *global_config = Some(config.clone());
     }
     
-    // Start the scheduler task
     let handle = tokio::spawn(scheduler_loop(config));
     
-    {
-        let mut scheduler_handle = SCHEDULER_HANDLE.lock().unwrap();
-        *scheduler_handle = Some(handle);
+    let mut scheduler_handle = SCHEDULER_HANDLE.lock().unwrap();
+    if let Some(previous) = scheduler_handle.replace(handle) {
+        previous.abort();
+    }
+}
+
+/// Brings the running scheduler in line with scheduler_config.json after it changed on disk,
+/// restarting it with the new interval or stopping it. The app's own saves match the running
+/// config and change nothing. Returns whether the schedule changed.
+pub fn reload_scheduler_config() -> bool {
+    let config = load_scheduler_config();
+    let previous = SCHEDULER_CONFIG.lock().unwrap().clone();
+    let is_running = SCHEDULER_HANDLE.lock().unwrap().is_some();
+    
+    let schedule_changed = match previous {
+        Some(previous) => previous.enabled != config.enabled || previous.interval_minutes != config.interval_minutes,
+        None => config.enabled != is_running,
+    };
+    if !schedule_changed {
+        return false;
+    }
+    
+    if config.enabled {
+        println!("Scheduler config changed on disk, restarting with {} minute intervals", config.interval_minutes);
+        spawn_scheduler(config);
+    } else {
+        println!("Scheduler disabled on disk, stopping");
+        *SCHEDULER_CONFIG.lock().unwrap() = Some(config);
+        if let Some(handle) = SCHEDULER_HANDLE.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+    
+    true
+}
+
+/// Runs the wallpaper check now instead of at the next tick, if the scheduler is running.
+/// With `reapply`, the chosen wallpaper is set again even if it is already the current one,
+/// e.g. because its file was replaced.
+pub fn request_wallpaper_refresh(reapply: bool) {
+    if SCHEDULER_HANDLE.lock().unwrap().is_none() {
+        return;
+    }
+    
+    let config = {
+        let mut global_config = SCHEDULER_CONFIG.lock().unwrap();
+        match global_config.as_mut() {
+            Some(global) if global.enabled => {
+                if reapply {
+                    global.last_applied_path = None;
+                }
+                global.clone()
+            }
+            _ => return,
+        }
+    };
+    
+    // A fresh loop checks immediately on its first tick
+    spawn_scheduler(config);
+}
+
+/// The wallpaper the scheduler last applied, if any.
+pub fn last_applied_wallpaper() -> Option<String> {
+    SCHEDULER_CONFIG
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|config| config.last_applied_path.clone())
+}
+
+#[tauri::command]
+pub async fn start_wallpaper_scheduler(interval_minutes: Option<u64>) -> Result<String, String> {
+    if declared_scheduler().enabled == Some(false) {
+        return Err("The scheduler is disabled in config.toml".to_string());
     }
     
+    let config = update_scheduler_config(|config| {
+        config.enabled = true;
+        config.interval_minutes = interval_minutes.unwrap_or(3);
+        apply_declared_scheduler(config);
+        end_manual_override(config);
+        Ok(())
+    })?;
+    let interval = config.interval_minutes;
+    
+    // Start the scheduler task, replacing the running one
+    spawn_scheduler(config);
+    
     Ok(format!("Wallpaper scheduler started with {} minute intervals", interval))
 }
 
 #[tauri::command]
 pub async fn stop_wallpaper_scheduler() -> Result<String, String> {
+    if declared_scheduler().enabled == Some(true) {
+        return Err("The scheduler is enabled in config.toml".to_string());
+    }
+    
     // Update config to disabled
-    let mut config = load_scheduler_config();
-    config.enabled = false;
-    save_scheduler_config(&config)?;
+    let config = update_scheduler_config(|config| {
+        config.enabled = false;
+        Ok(())
+    })?;
     
     {
         let mut global_config = SCHEDULER_CONFIG.lock().unwrap();
@@ -297,19 +579,8 @@ pub async fn initialize_scheduler() -> Result<String, String> {
         // Restart the scheduler if it was previously enabled
         println!("Restoring previously enabled scheduler with {} minute intervals", config.interval_minutes);
         
-        // Update global config
-        {
-            let mut global_config = SCHEDULER_CONFIG.lock().unwrap();
-            *global_config = Some(config.clone());
-        }
-        
         // Start the scheduler task
-        let handle = tokio::spawn(scheduler_loop(config.clone()));
-        
-        {
-            let mut scheduler_handle = SCHEDULER_HANDLE.lock().unwrap();
-            *scheduler_handle = Some(handle);
-        }
+        spawn_scheduler(config.clone());
         
         Ok(format!("Scheduler automatically restored with {} minute intervals", config.interval_minutes))
     } else {
@@ -330,12 +601,58 @@ pub async fn get_scheduler_status() -> Result<serde_json::Value, String> {
         "enabled": config.enabled,
         "interval_minutes": config.interval_minutes,
         "is_running": is_running,
-        "last_applied_path": config.last_applied_path
+        "last_applied_path": config.last_applied_path,
+        "external_change_action": config.external_change_action,
+        "manual_override": MANUAL_OVERRIDE.lock().unwrap().clone(),
+        "last_backup_at": auto_backup::load_status().last_success_at
     }))
 }
 
+/// Ends a pause caused by a manual wallpaper change and applies the scheduled wallpaper again.
+#[tauri::command]
+pub async fn resume_wallpaper_automation() -> Result<String, String> {
+    if MANUAL_OVERRIDE.lock().unwrap().take().is_none() {
+        return Ok("Automation is not paused".to_string());
+    }
+    request_wallpaper_refresh(true);
+    Ok("Wallpaper automation resumed".to_string())
+}
+
 #[tauri::command]
-pub async fn update_scheduler_collection_data(collection_data: Value) -> Result<String, String> {
+pub async fn set_external_change_action(action: String) -> Result<String, String> {
+    if action != "pause" && action != "reapply" {
+        return Err(format!("Unknown external change action: {}", action));
+    }
+
+    update_scheduler_config(|config| {
+        config.external_change_action = action.clone();
+        Ok(())
+    })?;
+
+    // The running loop picks the action up from the global config on its next tick
+    if let Some(global) = SCHEDULER_CONFIG.lock().unwrap().as_mut() {
+        global.external_change_action = action.clone();
+    }
+    Ok(format!("External wallpaper changes will {}", if action == "pause" { "pause automation" } else { "be undone" }))
+}
+
+/// The collections data last sent by the frontend, if any.
+pub fn get_collection_data() -> Option<Value> {
+    COLLECTION_DATA.lock().unwrap().clone()
+}
+
+/// Re-applies config.toml's collections to the synced collections data after it changed.
+pub fn refresh_declared_collections() {
+    let mut global_data = COLLECTION_DATA.lock().unwrap();
+    if let Some(collection_data) = global_data.as_mut() {
+        apply_declared_collections(collection_data);
+    }
+}
+
+#[tauri::command]
+pub async fn update_scheduler_collection_data(mut collection_data: Value) -> Result<String, String> {
+    apply_declared_collections(&mut collection_data);
+    
     // Store the collection data from the frontend for the scheduler to use
     {
         let mut global_data = COLLECTION_DATA.lock().unwrap();
@@ -343,4 +660,45 @@ pub async fn update_scheduler_collection_data(collection_data: Value) -> Result<
     }
     
     Ok("Collection data updated successfully".to_string())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // MANUAL_OVERRIDE is global
+    static OVERRIDE_LOCK: Mutex<()> = Mutex::new(());
+
+    fn applied_config(action: &str) -> SchedulerConfig {
+        SchedulerConfig {
+            enabled: true,
+            last_applied_path: Some("/wallpapers/rain.jpg".to_string()),
+            external_change_action: action.to_string(),
+            ..SchedulerConfig::default()
+        }
+    }
+
+    #[test]
+    fn manual_start_after_external_change_does_not_pause_again() {
+        let _guard = OVERRIDE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut config = applied_config("pause");
+
+        assert!(pause_for_external_change(&mut config, Path::new("/home/me/cat.png")));
+        assert!(MANUAL_OVERRIDE.lock().unwrap().is_some());
+
+        end_manual_override(&mut config);
+        assert!(MANUAL_OVERRIDE.lock().unwrap().is_none());
+        // Nothing to compare the screen against, so the first check can't detect a change
+        assert_eq!(config.last_applied_path, None);
+    }
+
+    #[test]
+    fn reapply_action_forgets_applied_wallpaper_without_pausing() {
+        let _guard = OVERRIDE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        *MANUAL_OVERRIDE.lock().unwrap() = None;
+        let mut config = applied_config("reapply");
+
+        assert!(!pause_for_external_change(&mut config, Path::new("/home/me/cat.png")));
+        assert!(MANUAL_OVERRIDE.lock().unwrap().is_none());
+        assert_eq!(config.last_applied_path, None);
+    }
+}
@@ -0,0 +1,387 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::Value;
+use crate::modules::persistence::lock_file;
+use crate::modules::utils::{get_app_config_dir, get_app_data_dir};
+
+// Encrypted-file fallback for systems without a Secret Service provider. The data key
+// lives in a separate owner-only file, which keeps the API key out of settings files,
+// synced folders and backups, but not away from someone with access to the data directory.
+const FALLBACK_SECRETS_FILE: &str = "secrets.enc";
+const FALLBACK_KEY_FILE: &str = ".secrets.key";
+const SETTINGS_FILE: &str = "app_settings.json";
+const REDACTED: &str = "[REDACTED]";
+
+// The API key as last loaded from or written to the secret store
+static API_KEY: Mutex<Option<String>> = Mutex::new(None);
+
+/// The weather API key held by the secret store, or an empty string if none is stored.
+pub fn cached_api_key() -> String {
+    API_KEY.lock().unwrap().clone().unwrap_or_default()
+}
+
+/// Whether `key` is safely held by the secret store, so it can be left out of settings files.
+pub fn is_api_key_stored(key: &str) -> bool {
+    API_KEY.lock().unwrap().as_deref() == Some(key)
+}
+
+/// Stores the API key in the Secret Service, falling back to the encrypted file.
+/// An empty key removes it from both.
+pub async fn store_api_key(api_key: &str) -> Result<(), String> {
+    match secret_store::store(api_key).await {
+        Ok(()) => remove_fallback_file(),
+        Err(e) => {
+            eprintln!("Secret Service unavailable, storing API key in encrypted file: {}", e);
+            if api_key.is_empty() {
+                remove_fallback_file();
+            } else {
+                write_fallback_file(api_key)?;
+            }
+        }
+    }
+
+    *API_KEY.lock().unwrap() = Some(api_key.to_string());
+    Ok(())
+}
+
+async fn load_stored_api_key() -> Option<String> {
+    match secret_store::load().await {
+        Ok(Some(api_key)) => return Some(api_key),
+        Ok(None) => {}
+        Err(e) => eprintln!("Secret Service unavailable, trying encrypted file: {}", redact_secrets(&e)),
+    }
+
+    match read_fallback_file() {
+        Ok(api_key) => api_key,
+        Err(e) => {
+            eprintln!("Failed to read encrypted secrets: {}", e);
+            None
+        }
+    }
+}
+
+/// Moves a plaintext API key out of app_settings.json into the secret store. Runs at
+/// startup and after anything that may write the settings file wholesale (backup import,
+/// rollback).
+pub async fn migrate_plaintext_api_key() -> Result<(), String> {
+    let settings_path = get_app_config_dir()?.join(SETTINGS_FILE);
+    let api_key = match read_settings_document(&settings_path)?
+        .as_ref()
+        .and_then(|settings| settings.get("weather_api_key"))
+        .and_then(|key| key.as_str())
+    {
+        Some(api_key) if !api_key.is_empty() => api_key.to_string(),
+        _ => return Ok(()),
+    };
+
+    store_api_key(&api_key).await?;
+
+    // Only drop the plaintext copy once the key is stored. Re-read under the lock so a
+    // settings save made while the key was being stored isn't overwritten.
+    let settings_lock = lock_file(&settings_path)?;
+    let mut settings = match read_settings_document(&settings_path)? {
+        Some(settings) => settings,
+        None => return Ok(()),
+    };
+    if settings.get("weather_api_key").and_then(|key| key.as_str()) != Some(api_key.as_str()) {
+        return Ok(());
+    }
+    settings["weather_api_key"] = Value::String(String::new());
+    settings_lock.write_json(&settings)
+        .map_err(|e| format!("Failed to write settings: {}", e))?;
+
+    println!("Moved weather API key from settings file to secret store");
+    Ok(())
+}
+
+fn read_settings_document(settings_path: &Path) -> Result<Option<Value>, String> {
+    match fs::read_to_string(settings_path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse settings: {}", e)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Migrates any plaintext key and loads the stored one into memory. Called once at startup
+/// so synchronous settings reads can fill in the key.
+pub async fn init_secrets() {
+    if let Err(e) = migrate_plaintext_api_key().await {
+        eprintln!("Warning: Failed to migrate API key to secret store: {}", e);
+    }
+
+    if API_KEY.lock().unwrap().is_none() {
+        let api_key = load_stored_api_key().await.unwrap_or_default();
+        *API_KEY.lock().unwrap() = Some(api_key);
+    }
+}
+
+/// Removes the API key from text that may be logged or shown, both the known key and any
+/// `key=` query parameter in a URL.
+pub fn redact_secrets(text: &str) -> String {
+    let mut redacted = text.to_string();
+
+    let api_key = cached_api_key();
+    if !api_key.is_empty() {
+        redacted = redacted.replace(&api_key, REDACTED);
+    }
+
+    let mut result = String::with_capacity(redacted.len());
+    let mut rest = redacted.as_str();
+    while let Some(index) = rest.find("key=") {
+        let is_parameter = index == 0 || matches!(rest.as_bytes()[index - 1], b'?' | b'&');
+        let value_start = index + "key=".len();
+        result.push_str(&rest[..value_start]);
+        rest = &rest[value_start..];
+
+        if is_parameter {
+            let value_end = rest
+                .find(|c: char| c == '&' || c == ')' || c.is_whitespace())
+                .unwrap_or(rest.len());
+            if value_end > 0 {
+                result.push_str(REDACTED);
+            }
+            rest = &rest[value_end..];
+        }
+    }
+    result.push_str(rest);
+
+    result
+}
+
+fn get_fallback_path(name: &str) -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join(name))
+}
+
+fn remove_fallback_file() {
+    if let Ok(path) = get_fallback_path(FALLBACK_SECRETS_FILE) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+fn read_key_file(key_path: &Path) -> Result<Option<[u8; 32]>, String> {
+    let encoded = match fs::read_to_string(key_path) {
+        Ok(encoded) => encoded,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read secrets key file: {}", e)),
+    };
+    let bytes = BASE64.decode(encoded.trim())
+        .map_err(|e| format!("Invalid secrets key file: {}", e))?;
+    bytes.try_into().map(Some).map_err(|_| "Invalid secrets key file".to_string())
+}
+
+// Data key for the fallback file, created on first use. Generated under the key file's
+// lock, so two writers can't each create a key and encrypt with the one that gets replaced.
+fn fallback_key() -> Result<[u8; 32], String> {
+    let key_path = get_fallback_path(FALLBACK_KEY_FILE)?;
+    if let Some(key) = read_key_file(&key_path)? {
+        return Ok(key);
+    }
+
+    let key_lock = lock_file(&key_path)?;
+    if let Some(key) = read_key_file(&key_path)? {
+        return Ok(key);
+    }
+
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    key_lock.write_private(BASE64.encode(key).as_bytes())
+        .map_err(|e| format!("Failed to write secrets key file: {}", e))?;
+    Ok(key)
+}
+
+/// Encrypts the API key with this machine's fallback data key, for keeping a copy (e.g. in
+/// an import rollback snapshot) without writing it out in plaintext.
+pub fn seal_api_key(api_key: &str) -> Result<Value, String> {
+    let key = fallback_key()?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, api_key.as_bytes())
+        .map_err(|_| "Failed to encrypt API key".to_string())?;
+
+    Ok(serde_json::json!({
+        "nonce": BASE64.encode(nonce),
+        "ciphertext": BASE64.encode(ciphertext),
+    }))
+}
+
+/// Decrypts a key sealed by `seal_api_key` on this machine.
+pub fn unseal_api_key(sealed: &Value) -> Result<String, String> {
+    let decode = |field: &str| {
+        sealed
+            .get(field)
+            .and_then(|value| value.as_str())
+            .and_then(|value| BASE64.decode(value).ok())
+            .ok_or_else(|| format!("Invalid encrypted secrets: missing {}", field))
+    };
+    let nonce = decode("nonce")?;
+    let ciphertext = decode("ciphertext")?;
+    if nonce.len() != 12 {
+        return Err("Invalid encrypted secrets: bad nonce".to_string());
+    }
+
+    let key = fallback_key()?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| "Failed to decrypt API key".to_string())?;
+
+    String::from_utf8(plaintext).map_err(|e| format!("Invalid encrypted secrets: {}", e))
+}
+
+fn write_fallback_file(api_key: &str) -> Result<(), String> {
+    let content = seal_api_key(api_key)?;
+    let path = get_fallback_path(FALLBACK_SECRETS_FILE)?;
+    lock_file(&path)?
+        .write_private(content.to_string().as_bytes())
+        .map_err(|e| format!("Failed to write encrypted secrets: {}", e))
+}
+
+fn read_fallback_file() -> Result<Option<String>, String> {
+    let path = get_fallback_path(FALLBACK_SECRETS_FILE)?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return Ok(None),
+    };
+
+    let stored: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid encrypted secrets: {}", e))?;
+    unseal_api_key(&stored).map(Some)
+}
+
+// Secret Service (GNOME Keyring, KWallet) over D-Bus
+#[cfg(target_os = "linux")]
+mod secret_store {
+    use std::collections::HashMap;
+    use secret_service::{EncryptionType, SecretService};
+
+    const ITEM_LABEL: &str = "Wallpaper Thing weather API key";
+
+    fn attributes() -> HashMap<&'static str, &'static str> {
+        HashMap::from([("application", "wallpaperthing"), ("secret", "weather_api_key")])
+    }
+
+    fn service_error(context: &str) -> impl Fn(secret_service::Error) -> String + '_ {
+        move |e| format!("Secret Service {}: {}", context, e)
+    }
+
+    pub async fn load() -> Result<Option<String>, String> {
+        let service = SecretService::connect(EncryptionType::Dh)
+            .await
+            .map_err(service_error("connection failed"))?;
+        let items = service
+            .search_items(attributes())
+            .await
+            .map_err(service_error("search failed"))?;
+
+        let item = match items.unlocked.first().or(items.locked.first()) {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+        if item.is_locked().await.unwrap_or(true) {
+            item.unlock().await.map_err(service_error("unlock failed"))?;
+        }
+
+        let secret = item.get_secret().await.map_err(service_error("read failed"))?;
+        String::from_utf8(secret)
+            .map(Some)
+            .map_err(|e| format!("Secret Service returned an invalid key: {}", e))
+    }
+
+    pub async fn store(api_key: &str) -> Result<(), String> {
+        let service = SecretService::connect(EncryptionType::Dh)
+            .await
+            .map_err(service_error("connection failed"))?;
+
+        if api_key.is_empty() {
+            let items = service
+                .search_items(attributes())
+                .await
+                .map_err(service_error("search failed"))?;
+            for item in items.unlocked.iter().chain(items.locked.iter()) {
+                item.delete().await.map_err(service_error("delete failed"))?;
+            }
+            return Ok(());
+        }
+
+        let collection = service
+            .get_default_collection()
+            .await
+            .map_err(service_error("no default collection"))?;
+        if collection.is_locked().await.unwrap_or(true) {
+            collection.unlock().await.map_err(service_error("unlock failed"))?;
+        }
+
+        collection
+            .create_item(ITEM_LABEL, attributes(), api_key.as_bytes(), true, "text/plain")
+            .await
+            .map_err(service_error("write failed"))?;
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod secret_store {
+    pub async fn load() -> Result<Option<String>, String> {
+        Err("Secret Service is only supported on Linux".to_string())
+    }
+
+    pub async fn store(_api_key: &str) -> Result<(), String> {
+        Err("Secret Service is only supported on Linux".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use crate::modules::utils::use_test_data_dir;
+
+    #[test]
+    fn concurrent_first_use_agrees_on_one_key() {
+        let _test_dir = use_test_data_dir();
+        let key_path = get_fallback_path(FALLBACK_KEY_FILE).unwrap();
+        let _ = fs::remove_file(&key_path);
+
+        let workers: Vec<_> = (0..8).map(|_| thread::spawn(|| fallback_key().unwrap())).collect();
+        let keys: Vec<[u8; 32]> = workers.into_iter().map(|worker| worker.join().unwrap()).collect();
+
+        assert!(keys.iter().all(|key| *key == keys[0]));
+        assert_eq!(read_key_file(&key_path).unwrap(), Some(keys[0]));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&key_path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn fallback_file_round_trips_and_is_owner_only() {
+        let _test_dir = use_test_data_dir();
+        write_fallback_file("abc123").unwrap();
+        assert_eq!(read_fallback_file().unwrap().as_deref(), Some("abc123"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let path = get_fallback_path(FALLBACK_SECRETS_FILE).unwrap();
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn sealed_key_round_trips_including_no_key() {
+        let _test_dir = use_test_data_dir();
+        for api_key in ["abc123", ""] {
+            let sealed = seal_api_key(api_key).unwrap();
+            assert!(!sealed.to_string().contains("abc123"));
+            assert_eq!(unseal_api_key(&sealed).unwrap(), api_key);
+        }
+    }
+}
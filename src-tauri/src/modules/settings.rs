@@ -1,29 +1,160 @@
-use std::fs;
+use std::path::Path;
 use serde::{Serialize, Deserialize};
-use crate::modules::utils::get_app_data_dir;
+use crate::modules::utils::get_app_config_dir;
+use crate::modules::config_migration::{insert_missing_fields, load_versioned, Migration};
+use crate::modules::declarative_config::{apply_declared_settings, keep_file_values, managed_settings_fields};
+use crate::modules::persistence::{lock_file, FileLock};
+use crate::modules::wallpaper_backend::AUTO_BACKEND;
+use crate::modules::secrets::{cached_api_key, is_api_key_stored, redact_secrets, store_api_key};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LocationProfile {
+    pub id: String,
+    pub name: String, // e.g. "Home", "Office"
+    pub location: String, // Display name of the place
+    pub latitude: f64,
+    pub longitude: f64,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AppSettings {
+    #[serde(default)]
+    pub schema_version: u32, // Set to SETTINGS_SCHEMA_VERSION on save
     pub weather_api_key: String,
     pub location: String,
     pub use_auto_location: bool,
     pub cache_duration_minutes: u64,
+    #[serde(default)]
+    pub alert_notifications_enabled: bool,
+    #[serde(default = "default_aqi_smog_threshold")]
+    pub aqi_smog_threshold: i32,
+    #[serde(default = "default_pm25_smog_threshold")]
+    pub pm25_smog_threshold: f32,
+    #[serde(default = "default_pollen_high_threshold")]
+    pub pollen_high_threshold: f32,
+    #[serde(default)]
+    pub latitude: Option<f64>, // Explicit coordinates, preferred over the free-text location
+    #[serde(default)]
+    pub longitude: Option<f64>,
+    #[serde(default)]
+    pub location_profiles: Vec<LocationProfile>,
+    #[serde(default)]
+    pub active_location_profile: Option<String>, // Profile id, overrides the fields above
+    #[serde(default = "default_true")]
+    pub native_geolocation_enabled: bool, // Use GeoClue for auto-location on Linux before IP lookup
+    #[serde(default = "default_geolocation_accuracy")]
+    pub geolocation_accuracy: String, // "country", "city", "neighborhood", "street" or "exact"
+    #[serde(default = "default_geolocation_refresh_minutes")]
+    pub geolocation_refresh_minutes: u64,
+    #[serde(default)]
+    pub auto_backup_enabled: bool,
+    #[serde(default = "default_auto_backup_frequency")]
+    pub auto_backup_frequency: String, // "daily" or "weekly"
+    #[serde(default)]
+    pub auto_backup_directory: Option<String>, // Defaults to "backups" in the app data directory
+    #[serde(default = "default_auto_backup_retention")]
+    pub auto_backup_retention: u32, // Number of automatic backups to keep
+    #[serde(default)]
+    pub auto_backup_include_secrets: bool, // Off by default since backup folders are often shared
+    #[serde(default = "default_wallpaper_backend")]
+    pub wallpaper_backend: String, // "auto" or a backend id such as "gnome" or "swww"
+    #[serde(default = "default_wallpaper_transition")]
+    pub wallpaper_transition: String, // "none", "crossfade", "wipe" or "grow"
+    #[serde(default = "default_wallpaper_transition_duration_ms")]
+    pub wallpaper_transition_duration_ms: u64,
+}
+
+pub const SETTINGS_SCHEMA_VERSION: u32 = 1;
+
+// Ordered migrations; SETTINGS_MIGRATIONS[n] upgrades a version n file to n + 1
+const SETTINGS_MIGRATIONS: [Migration; 1] = [
+    // 0 -> 1: files written before versioning may lack the original required fields
+    |settings| insert_missing_fields(settings, serde_json::json!({
+        "weather_api_key": "",
+        "location": "",
+        "use_auto_location": true,
+        "cache_duration_minutes": 60,
+    })),
+];
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_geolocation_accuracy() -> String {
+    "city".to_string()
+}
+
+fn default_geolocation_refresh_minutes() -> u64 {
+    60
+}
+
+fn default_auto_backup_frequency() -> String {
+    "daily".to_string()
+}
+
+fn default_auto_backup_retention() -> u32 {
+    7
+}
+
+fn default_wallpaper_backend() -> String {
+    AUTO_BACKEND.to_string()
+}
+
+fn default_wallpaper_transition() -> String {
+    "none".to_string()
+}
+
+fn default_wallpaper_transition_duration_ms() -> u64 {
+    1000
+}
+
+fn default_aqi_smog_threshold() -> i32 {
+    4 // US EPA "Unhealthy"
+}
+
+fn default_pm25_smog_threshold() -> f32 {
+    55.5 // µg/m³, lower bound of the EPA "Unhealthy" band
+}
+
+fn default_pollen_high_threshold() -> f32 {
+    50.0 // grains/m³
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
+            schema_version: SETTINGS_SCHEMA_VERSION,
             weather_api_key: String::new(),
             location: String::new(),
             use_auto_location: true,
             cache_duration_minutes: 60, // Default to 60 minutes
+            alert_notifications_enabled: false,
+            aqi_smog_threshold: default_aqi_smog_threshold(),
+            pm25_smog_threshold: default_pm25_smog_threshold(),
+            pollen_high_threshold: default_pollen_high_threshold(),
+            latitude: None,
+            longitude: None,
+            location_profiles: Vec::new(),
+            active_location_profile: None,
+            native_geolocation_enabled: true,
+            geolocation_accuracy: default_geolocation_accuracy(),
+            geolocation_refresh_minutes: default_geolocation_refresh_minutes(),
+            auto_backup_enabled: false,
+            auto_backup_frequency: default_auto_backup_frequency(),
+            auto_backup_directory: None,
+            auto_backup_retention: default_auto_backup_retention(),
+            auto_backup_include_secrets: false,
+            wallpaper_backend: default_wallpaper_backend(),
+            wallpaper_transition: default_wallpaper_transition(),
+            wallpaper_transition_duration_ms: default_wallpaper_transition_duration_ms(),
         }
     }
 }
 
 fn get_settings_file_path() -> Result<std::path::PathBuf, String> {
-    let app_dir = get_app_data_dir()?;
-    Ok(app_dir.join("app_settings.json"))
+    let config_dir = get_app_config_dir()?;
+    Ok(config_dir.join("app_settings.json"))
 }
 
 pub fn load_app_settings() -> AppSettings {
@@ -32,22 +163,62 @@ pub fn load_app_settings() -> AppSettings {
         Err(_) => return AppSettings::default(),
     };
 
-    if let Ok(content) = fs::read_to_string(settings_path) {
-        serde_json::from_str(&content).unwrap_or_default()
-    } else {
-        AppSettings::default()
+    let (settings, needs_save) = read_app_settings(&settings_path);
+    
+    // Write back migrated or recovered settings so the file is readable as-is next time
+    if needs_save {
+        if let Err(e) = save_app_settings(&settings) {
+            eprintln!("Warning: Failed to save migrated settings: {}", e);
+        }
     }
+    settings
+}
+
+// Settings as the app sees them, and whether the file needs writing back after a migration
+fn read_app_settings(settings_path: &Path) -> (AppSettings, bool) {
+    let loaded = load_versioned::<AppSettings>(settings_path, SETTINGS_SCHEMA_VERSION, &SETTINGS_MIGRATIONS);
+    let mut settings = loaded.value;
+
+    // The key lives in the secret store; a plaintext one is only left until it's migrated
+    if settings.weather_api_key.is_empty() {
+        settings.weather_api_key = cached_api_key();
+    }
+    (apply_declared_settings(settings), loaded.needs_save)
 }
 
 pub fn save_app_settings(settings: &AppSettings) -> Result<(), String> {
     let settings_path = get_settings_file_path()?;
-    let content = serde_json::to_string_pretty(settings)
+    write_app_settings(&lock_file(&settings_path)?, &settings_path, settings)
+}
+
+/// Loads the settings, applies `update` and saves the result, holding the settings file's
+/// lock throughout so concurrent updates can't overwrite each other's changes. Nothing is
+/// written if `update` fails.
+pub fn update_app_settings<T>(update: impl FnOnce(&mut AppSettings) -> Result<T, String>) -> Result<T, String> {
+    let settings_path = get_settings_file_path()?;
+    let settings_lock = lock_file(&settings_path)?;
+
+    let (mut settings, _) = read_app_settings(&settings_path);
+    let result = update(&mut settings)?;
+    write_app_settings(&settings_lock, &settings_path, &settings)?;
+    Ok(result)
+}
+
+fn write_app_settings(settings_lock: &FileLock, settings_path: &Path, settings: &AppSettings) -> Result<(), String> {
+    // Never write the key to disk once the secret store holds it
+    let mut settings = settings.clone();
+    settings.schema_version = SETTINGS_SCHEMA_VERSION;
+    if is_api_key_stored(&settings.weather_api_key) {
+        settings.weather_api_key.clear();
+    }
+
+    // Fields declared in config.toml keep their previous values in the JSON file
+    let mut document = serde_json::to_value(&settings)
         .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-    
-    fs::write(settings_path, content)
-        .map_err(|e| format!("Failed to write settings: {}", e))?;
-    
-    Ok(())
+    keep_file_values(&mut document, settings_path, &managed_settings_fields());
+
+    settings_lock.write_json(&document)
+        .map_err(|e| format!("Failed to write settings: {}", e))
 }
 
 #[tauri::command]
@@ -57,6 +228,9 @@ pub async fn get_app_settings() -> Result<AppSettings, String> {
 
 #[tauri::command]
 pub async fn save_app_settings_cmd(settings: AppSettings) -> Result<String, String> {
+    if !is_api_key_stored(&settings.weather_api_key) {
+        store_api_key(&settings.weather_api_key).await?;
+    }
     save_app_settings(&settings)?;
     Ok("Settings saved successfully".to_string())
 }
@@ -71,7 +245,7 @@ pub async fn test_weather_api(api_key: String, location: String) -> Result<Strin
     
     let response = reqwest::get(&url)
         .await
-        .map_err(|e| format!("HTTP error: {}", e))?;
+        .map_err(|e| redact_secrets(&format!("HTTP error: {}", e)))?;
     
     if !response.status().is_success() {
         return Err(format!("API test failed: {}", response.status()));
@@ -80,7 +254,55 @@ pub async fn test_weather_api(api_key: String, location: String) -> Result<Strin
     // Try to parse the response to ensure it's valid
     let _weather_data: serde_json::Value = response.json()
         .await
-        .map_err(|e| format!("Invalid API response: {}", e))?;
+        .map_err(|e| redact_secrets(&format!("Invalid API response: {}", e)))?;
 
     Ok("API key test successful".to_string())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use crate::modules::utils::use_test_data_dir;
+
+    fn profile(id: String) -> LocationProfile {
+        LocationProfile { name: id.clone(), id, location: "Somewhere".to_string(), latitude: 0.0, longitude: 0.0 }
+    }
+
+    #[test]
+    fn concurrent_updates_keep_each_others_changes() {
+        let _test_dir = use_test_data_dir();
+        save_app_settings(&AppSettings::default()).unwrap();
+
+        let workers: Vec<_> = (0..2)
+            .map(|worker| {
+                thread::spawn(move || {
+                    for index in 0..20 {
+                        update_app_settings(|settings| {
+                            settings.location_profiles.push(profile(format!("{}-{}", worker, index)));
+                            Ok(())
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert_eq!(load_app_settings().location_profiles.len(), 40);
+    }
+
+    #[test]
+    fn failed_update_writes_nothing() {
+        let _test_dir = use_test_data_dir();
+        save_app_settings(&AppSettings::default()).unwrap();
+
+        let result: Result<(), String> = update_app_settings(|settings| {
+            settings.location_profiles.push(profile("discarded".to_string()));
+            Err("no".to_string())
+        });
+        assert!(result.is_err());
+        assert!(load_app_settings().location_profiles.is_empty());
+    }
+}
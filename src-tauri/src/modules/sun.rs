@@ -0,0 +1,105 @@
+use chrono::{DateTime as ChronoDateTime, Datelike, Duration, Local, NaiveDate, Utc};
+use crate::modules::types::{AstroData, CurrentConditions};
+use crate::modules::time::get_time_period_at;
+use crate::modules::moon::{get_moon_categories, moon_illumination_for, moon_phase_for};
+use crate::modules::weather::build_active_categories;
+use crate::modules::location::{get_active_profile, resolve_coordinates};
+use crate::modules::settings::load_app_settings;
+
+// Official zenith for sunrise/sunset, accounting for refraction and the solar disc
+const SUN_ZENITH_DEGREES: f64 = 90.833;
+
+/// Sunrise or sunset in UTC for the given coordinates and date, using the NOAA
+/// almanac algorithm. None during polar day/night.
+fn sun_event_utc(latitude: f64, longitude: f64, date: NaiveDate, sunrise: bool) -> Option<ChronoDateTime<Utc>> {
+    let day_of_year = date.ordinal() as f64;
+    let longitude_hour = longitude / 15.0;
+    let approx_time = day_of_year + (if sunrise { 6.0 } else { 18.0 } - longitude_hour) / 24.0;
+
+    let mean_anomaly = 0.9856 * approx_time - 3.289;
+    let true_longitude = (mean_anomaly
+        + 1.916 * mean_anomaly.to_radians().sin()
+        + 0.020 * (2.0 * mean_anomaly).to_radians().sin()
+        + 282.634)
+        .rem_euclid(360.0);
+
+    let mut right_ascension = (0.91764 * true_longitude.to_radians().tan()).atan().to_degrees().rem_euclid(360.0);
+    // Right ascension must be in the same quadrant as the true longitude
+    right_ascension += (true_longitude / 90.0).floor() * 90.0 - (right_ascension / 90.0).floor() * 90.0;
+    right_ascension /= 15.0;
+
+    let sin_declination = 0.39782 * true_longitude.to_radians().sin();
+    let cos_declination = sin_declination.asin().cos();
+
+    let cos_hour_angle = (SUN_ZENITH_DEGREES.to_radians().cos() - sin_declination * latitude.to_radians().sin())
+        / (cos_declination * latitude.to_radians().cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+
+    let hour_angle = if sunrise {
+        360.0 - cos_hour_angle.acos().to_degrees()
+    } else {
+        cos_hour_angle.acos().to_degrees()
+    } / 15.0;
+
+    let local_mean_time = hour_angle + right_ascension - 0.06571 * approx_time - 6.622;
+    let utc_hours = (local_mean_time - longitude_hour).rem_euclid(24.0);
+
+    let midnight = date.and_hms_opt(0, 0, 0)?.and_utc();
+    Some(midnight + Duration::seconds((utc_hours * 3600.0) as i64))
+}
+
+/// Sunrise and sunset for today at the given coordinates, formatted like WeatherAPI
+/// ("06:42 AM") so they can be fed to the existing time period helpers.
+pub fn calculate_sun_times(latitude: f64, longitude: f64) -> Option<(String, String)> {
+    let today = Local::now().date_naive();
+    let format = |dt: ChronoDateTime<Utc>| dt.with_timezone(&Local).format("%I:%M %p").to_string();
+
+    let sunrise = sun_event_utc(latitude, longitude, today, true)?;
+    let sunset = sun_event_utc(latitude, longitude, today, false)?;
+    Some((format(sunrise), format(sunset)))
+}
+
+/// Conditions derived purely from the configured coordinates (sun and moon, no weather).
+/// Last-resort fallback for the scheduler when neither live nor cached weather is available.
+pub fn get_offline_conditions() -> Option<CurrentConditions> {
+    let settings = load_app_settings();
+    let (latitude, longitude) = resolve_coordinates(&settings)?;
+    let (sunrise, sunset) = calculate_sun_times(latitude, longitude)?;
+
+    let now = Local::now();
+    let astro = AstroData {
+        sunrise: sunrise.clone(),
+        sunset: sunset.clone(),
+        moonrise: None,
+        moonset: None,
+        moon_phase: None,
+        moon_illumination: None,
+    };
+
+    let time_period = get_time_period_at(now, Some(&sunrise), Some(&sunset));
+    let moon_categories = get_moon_categories(&astro, &now, &time_period);
+    let active_categories = build_active_categories(&[], &None, &moon_categories, &time_period);
+
+    let location = get_active_profile(&settings)
+        .map(|profile| profile.location.clone())
+        .unwrap_or_else(|| format!("{:.4}, {:.4}", latitude, longitude));
+
+    Some(CurrentConditions {
+        weather_condition: None,
+        time_period,
+        temperature: None,
+        humidity: None,
+        sunrise: Some(sunrise),
+        sunset: Some(sunset),
+        location: Some(location),
+        active_categories,
+        alerts: Vec::new(),
+        air_quality_index: None,
+        pm2_5: None,
+        pollen_count: None,
+        moon_phase: Some(moon_phase_for(&astro, &now)),
+        moon_illumination: Some(moon_illumination_for(&astro, &now)),
+    })
+}
@@ -1,4 +1,4 @@
-use chrono::{Local, Timelike, NaiveTime, DateTime as ChronoDateTime};
+use chrono::{Local, Timelike, NaiveDate, NaiveTime, DateTime as ChronoDateTime};
 use crate::modules::types::TimePeriodDetails;
 
 pub fn timestamp_to_time_string(timestamp: i64) -> String {
@@ -115,21 +115,29 @@ pub fn calculate_time_periods(sunrise_str: Option<&str>, sunset_str: Option<&str
 }
 
 fn parse_time_to_timestamp(time_str: &str) -> Option<i64> {
+    parse_time_to_timestamp_on(time_str, Local::now().date_naive())
+}
+
+pub fn parse_time_to_timestamp_on(time_str: &str, date: NaiveDate) -> Option<i64> {
     // Parse "HH:MM AM/PM" format from WeatherAPI
     NaiveTime::parse_from_str(time_str, "%I:%M %p").ok()
         .and_then(|time| {
-            let now = Local::now();
-            let today = now.date_naive();
-            today.and_time(time).and_local_timezone(Local).single().map(|dt| dt.timestamp())
+            date.and_time(time).and_local_timezone(Local).single().map(|dt| dt.timestamp())
         })
 }
 
 pub fn get_time_period_with_sun(sunrise_str: Option<&str>, sunset_str: Option<&str>) -> String {
-    let now = Local::now();
+    get_time_period_at(Local::now(), sunrise_str, sunset_str)
+}
+
+// Same as get_time_period_with_sun, but for an arbitrary moment (used for forecast hours).
+// Sunrise/sunset strings are interpreted on the date of `now`.
+pub fn get_time_period_at(now: ChronoDateTime<Local>, sunrise_str: Option<&str>, sunset_str: Option<&str>) -> String {
     let current_timestamp = now.timestamp();
+    let date = now.date_naive();
     
     if let (Some(sunrise_str), Some(sunset_str)) = (sunrise_str, sunset_str) {
-        if let (Some(sunrise), Some(sunset)) = (parse_time_to_timestamp(sunrise_str), parse_time_to_timestamp(sunset_str)) {
+        if let (Some(sunrise), Some(sunset)) = (parse_time_to_timestamp_on(sunrise_str, date), parse_time_to_timestamp_on(sunset_str, date)) {
             let dawn_start = sunrise - 1800; // 30 min before sunrise
             let dawn_end = sunrise + 1800;   // 30 min after sunrise (end of dawn)
             let dusk_start = sunset - 3600;  // 1 hour before sunset (start of evening)
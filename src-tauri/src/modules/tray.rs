@@ -0,0 +1,67 @@
+use tauri::{
+    menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
+    Manager, Runtime,
+};
+use crate::modules::location::set_active_location_profile;
+use crate::modules::scheduler::request_wallpaper_refresh;
+use crate::modules::settings::load_app_settings;
+
+const LOCATION_ITEM_PREFIX: &str = "location:";
+const DEFAULT_LOCATION_ITEM_ID: &str = "location:default";
+
+/// Builds the tray context menu. Rebuilt on every right click so it always
+/// reflects the current location profiles.
+pub fn build_tray_menu<R: Runtime, M: Manager<R>>(manager: &M) -> tauri::Result<Menu<R>> {
+    let settings = load_app_settings();
+    let active_id = settings.active_location_profile.clone();
+
+    let mut location_items = vec![CheckMenuItem::with_id(
+        manager,
+        DEFAULT_LOCATION_ITEM_ID,
+        "Default location",
+        true,
+        active_id.is_none(),
+        None::<&str>,
+    )?];
+
+    for profile in &settings.location_profiles {
+        location_items.push(CheckMenuItem::with_id(
+            manager,
+            format!("{}{}", LOCATION_ITEM_PREFIX, profile.id),
+            &profile.name,
+            true,
+            active_id.as_ref() == Some(&profile.id),
+            None::<&str>,
+        )?);
+    }
+
+    let location_refs: Vec<&dyn IsMenuItem<R>> = location_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<R>)
+        .collect();
+    let location_menu = Submenu::with_items(manager, "Location", true, &location_refs)?;
+
+    let separator = PredefinedMenuItem::separator(manager)?;
+    let quit_i = MenuItem::with_id(manager, "quit", "Quit", true, None::<&str>)?;
+
+    Menu::with_items(manager, &[&location_menu, &separator, &quit_i])
+}
+
+/// Handles location profile items from the tray menu. Returns false for ids it doesn't own.
+pub fn handle_location_menu_event(id: &str) -> bool {
+    let profile_id = match id.strip_prefix(LOCATION_ITEM_PREFIX) {
+        Some(_) if id == DEFAULT_LOCATION_ITEM_ID => None,
+        Some(profile_id) => Some(profile_id.to_string()),
+        None => return false,
+    };
+
+    match set_active_location_profile(profile_id) {
+        // The wallpaper follows the weather, which is now for a different place. Menu
+        // events arrive outside the async runtime, which the scheduler needs.
+        Ok(_) => {
+            tauri::async_runtime::spawn(async { request_wallpaper_refresh(false) });
+        }
+        Err(e) => eprintln!("Failed to switch location profile: {}", e),
+    }
+    true
+}
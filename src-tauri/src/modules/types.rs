@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 // WeatherAPI.com response structures
 #[derive(Serialize, Deserialize, Clone)]
@@ -6,6 +7,10 @@ pub struct WeatherApiResponse {
     pub location: WeatherLocation,
     pub current: WeatherCurrent,
     pub astronomy: WeatherAstronomy,
+    #[serde(default)]
+    pub forecast: Option<WeatherForecast>,
+    #[serde(default)]
+    pub alerts: Option<WeatherAlerts>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -20,6 +25,39 @@ pub struct WeatherCurrent {
     pub temp_c: f32,
     pub humidity: i32,
     pub condition: WeatherCondition,
+    #[serde(default)]
+    pub air_quality: Option<AirQuality>,
+    #[serde(default)]
+    pub pollen: Option<PollenData>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AirQuality {
+    #[serde(default)]
+    pub pm2_5: Option<f32>, // µg/m³
+    #[serde(default)]
+    pub pm10: Option<f32>, // µg/m³
+    #[serde(default, rename = "us-epa-index")]
+    pub us_epa_index: Option<i32>, // 1 (Good) to 6 (Hazardous)
+}
+
+// Pollen counts in grains/m³, only returned by some WeatherAPI plans
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PollenData {
+    #[serde(default, rename = "Hazel")]
+    pub hazel: Option<f32>,
+    #[serde(default, rename = "Alder")]
+    pub alder: Option<f32>,
+    #[serde(default, rename = "Birch")]
+    pub birch: Option<f32>,
+    #[serde(default, rename = "Oak")]
+    pub oak: Option<f32>,
+    #[serde(default, rename = "Grass")]
+    pub grass: Option<f32>,
+    #[serde(default, rename = "Mugwort")]
+    pub mugwort: Option<f32>,
+    #[serde(default, rename = "Ragweed")]
+    pub ragweed: Option<f32>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -37,6 +75,80 @@ pub struct WeatherAstronomy {
 pub struct AstroData {
     pub sunrise: String,
     pub sunset: String,
+    #[serde(default)]
+    pub moonrise: Option<String>, // "HH:MM AM/PM" or "No moonrise"
+    #[serde(default)]
+    pub moonset: Option<String>,
+    #[serde(default)]
+    pub moon_phase: Option<String>, // e.g. "Waxing Gibbous"
+    #[serde(default, deserialize_with = "deserialize_lenient_f32")]
+    pub moon_illumination: Option<f32>, // Percent
+}
+
+// WeatherAPI has returned moon_illumination both as a number and as a numeric string
+fn deserialize_lenient_f32<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(match value {
+        Some(serde_json::Value::Number(n)) => n.as_f64().map(|v| v as f32),
+        Some(serde_json::Value::String(s)) => s.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WeatherForecast {
+    pub forecastday: Vec<ForecastDay>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ForecastDay {
+    pub date: String, // "YYYY-MM-DD" in the location's local time
+    pub astro: AstroData,
+    pub hour: Vec<ForecastHour>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ForecastHour {
+    pub time_epoch: i64,
+    pub temp_c: f32,
+    pub humidity: i32,
+    pub condition: WeatherCondition,
+    #[serde(default)]
+    pub chance_of_rain: i32,
+    #[serde(default)]
+    pub chance_of_snow: i32,
+    #[serde(default)]
+    pub air_quality: Option<AirQuality>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WeatherAlerts {
+    #[serde(default)]
+    pub alert: Vec<WeatherAlert>,
+}
+
+// Government alert as returned by WeatherAPI; most fields are optional depending on the issuing agency
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WeatherAlert {
+    #[serde(default)]
+    pub headline: String,
+    #[serde(default)]
+    pub event: String,
+    #[serde(default)]
+    pub severity: String,
+    #[serde(default)]
+    pub urgency: String,
+    #[serde(default)]
+    pub areas: String,
+    #[serde(default)]
+    pub effective: String, // RFC 3339
+    #[serde(default)]
+    pub expires: String, // RFC 3339
+    #[serde(default)]
+    pub desc: String,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -49,6 +161,39 @@ pub struct CurrentConditions {
     pub sunset: Option<String>,
     pub location: Option<String>,
     pub active_categories: Vec<String>,
+    #[serde(default)]
+    pub alerts: Vec<WeatherAlert>, // Alerts in effect right now
+    #[serde(default)]
+    pub air_quality_index: Option<i32>, // US EPA index
+    #[serde(default)]
+    pub pm2_5: Option<f32>,
+    #[serde(default)]
+    pub pollen_count: Option<f32>, // Highest count across pollen types
+    #[serde(default)]
+    pub moon_phase: Option<String>,
+    #[serde(default)]
+    pub moon_illumination: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ForecastTimelineEntry {
+    pub timestamp: i64,
+    pub time: String, // "HH:MM" local time
+    pub weather_condition: Option<String>,
+    pub time_period: String,
+    pub temperature: f32,
+    pub chance_of_rain: i32,
+    pub chance_of_snow: i32,
+    pub active_categories: Vec<String>,
+    pub wallpaper_category: Option<String>, // Category the scheduler would pick
+    pub wallpaper_path: Option<String>,
+    pub is_change: bool, // Wallpaper differs from the previous hour
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ForecastTimelineResponse {
+    pub location: Option<String>,
+    pub entries: Vec<ForecastTimelineEntry>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -73,5 +218,26 @@ pub struct WeatherCache {
     pub data: WeatherApiResponse,
     pub cached_at: i64, // UTC timestamp
     pub location_key: String, // To handle different locations
+    #[serde(default)]
+    pub last_accessed: i64, // UTC timestamp, used for LRU eviction
+    #[serde(default)]
+    pub ttl_seconds: i64, // Validity window captured when the entry was written
+}
+
+// Keyed store persisted in weather_cache.json (location_key -> entry)
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct WeatherCacheStore {
+    pub entries: HashMap<String, WeatherCache>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WeatherCacheEntryInfo {
+    pub location_key: String,
+    pub location: String,
+    pub cached_at: i64,
+    pub last_accessed: i64,
+    pub age_seconds: i64,
+    pub ttl_seconds: i64,
+    pub is_expired: bool,
 }
 
@@ -1,27 +1,163 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::sync::Mutex;
 use dirs;
 
+const APP_DIR_NAME: &str = "wallpaperthing";
+
+/// Environment variable that puts all of the app's files in one directory, for portable
+/// installs and isolated test instances. The `--data-dir <path>` flag takes precedence.
+const DATA_DIR_ENV: &str = "WALLPAPERTHING_DATA_DIR";
+const DATA_DIR_FLAG: &str = "--data-dir";
+
+// Files that moved out of the data directory into the config and cache directories
+const CONFIG_FILES: [&str; 2] = ["app_settings.json", "scheduler_config.json"];
+const CACHE_FILES: [&str; 2] = ["weather_cache.json", "geolocation_cache.json"];
+
+static DATA_DIR_OVERRIDE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+fn flag_value(args: &[String]) -> Option<String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == DATA_DIR_FLAG {
+            return args.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix(DATA_DIR_FLAG).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Reads the data directory override from the command line or environment. Call once at
+/// startup, before anything touches the app's files.
+pub fn init_data_dir_override() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let value = flag_value(&args).or_else(|| std::env::var(DATA_DIR_ENV).ok());
+
+    if let Some(value) = value.filter(|value| !value.trim().is_empty()) {
+        // Relative paths would change meaning with the working directory (e.g. under autostart)
+        let path = std::path::absolute(&value).unwrap_or_else(|_| PathBuf::from(&value));
+        println!("Using data directory {}", path.display());
+        *DATA_DIR_OVERRIDE.lock().unwrap() = Some(path);
+    }
+}
+
+/// Arguments that reproduce the current override, so autostart launches use the same directory.
+pub fn data_dir_override_args() -> Vec<String> {
+    match DATA_DIR_OVERRIDE.lock().unwrap().as_ref() {
+        Some(path) => vec![DATA_DIR_FLAG.to_string(), path.to_string_lossy().to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// A fresh data directory for one test. Every app directory points into it while it
+/// lives, and tests using one run one at a time, since the override is process-wide.
+#[cfg(test)]
+pub struct TestDataDir {
+    root: tempfile::TempDir,
+    _guard: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+impl TestDataDir {
+    /// The directory the app's files go to
+    pub fn path(&self) -> PathBuf {
+        self.root.path().join("data")
+    }
+
+    /// The directory around it, where nothing should ever be written
+    pub fn outside(&self) -> &Path {
+        self.root.path()
+    }
+}
+
+#[cfg(test)]
+pub fn use_test_data_dir() -> TestDataDir {
+    static TEST_DIR_LOCK: Mutex<()> = Mutex::new(());
+    let guard = TEST_DIR_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let test_dir = TestDataDir { root: tempfile::tempdir().expect("temp dir"), _guard: guard };
+    fs::create_dir(test_dir.path()).expect("test data dir");
+    *DATA_DIR_OVERRIDE.lock().unwrap() = Some(test_dir.path());
+    test_dir
+}
+
+// The override directory if one is set, otherwise the app's directory under `base`
+fn resolve_app_dir(base: Option<PathBuf>, kind: &str) -> Result<PathBuf, String> {
+    let dir = match DATA_DIR_OVERRIDE.lock().unwrap().clone() {
+        Some(dir) => dir,
+        None => base
+            .ok_or(format!("Could not find {} directory", kind))?
+            .join(APP_DIR_NAME),
+    };
+
+    if !dir.exists() {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create app {} directory: {}", kind, e))?;
+    }
+
+    Ok(dir)
+}
+
+/// Wallpapers, backups and other state ($XDG_DATA_HOME/wallpaperthing).
 pub fn get_app_data_dir() -> Result<PathBuf, String> {
-    let data_dir = dirs::data_dir()
-        .ok_or("Could not find data directory")?
-        .join("wallpaperthing");
-    
-    if !data_dir.exists() {
-        fs::create_dir_all(&data_dir)
-            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    resolve_app_dir(dirs::data_dir(), "data")
+}
+
+/// Settings, scheduler config and config.toml ($XDG_CONFIG_HOME/wallpaperthing).
+pub fn get_app_config_dir() -> Result<PathBuf, String> {
+    resolve_app_dir(dirs::config_dir(), "config")
+}
+
+/// Weather and location caches, safe to delete ($XDG_CACHE_HOME/wallpaperthing).
+pub fn get_app_cache_dir() -> Result<PathBuf, String> {
+    resolve_app_dir(dirs::cache_dir(), "cache")
+}
+
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    // The directories may be on different filesystems, where rename fails
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).map_err(|e| e.to_string())?;
+    fs::remove_file(from).map_err(|e| e.to_string())
+}
+
+fn migrate_files(names: &[&str], from: &Path, to: &Path) {
+    for name in names {
+        let (source, target) = (from.join(name), to.join(name));
+        if !source.exists() || target.exists() {
+            continue;
+        }
+        match move_file(&source, &target) {
+            Ok(()) => println!("Moved {} to {}", name, to.display()),
+            Err(e) => eprintln!("Warning: Failed to move {} to {}: {}", name, to.display(), e),
+        }
+    }
+}
+
+/// Moves settings and caches written by older versions, which kept everything in the data
+/// directory, to the config and cache directories. Called once at startup.
+pub fn migrate_legacy_layout() {
+    let (data_dir, config_dir, cache_dir) = match (get_app_data_dir(), get_app_config_dir(), get_app_cache_dir()) {
+        (Ok(data), Ok(config), Ok(cache)) => (data, config, cache),
+        _ => return,
+    };
+
+    if config_dir != data_dir {
+        migrate_files(&CONFIG_FILES, &data_dir, &config_dir);
+    }
+    if cache_dir != data_dir {
+        migrate_files(&CACHE_FILES, &data_dir, &cache_dir);
     }
-    
-    Ok(data_dir)
 }
 
 pub fn get_cache_file_path() -> Result<PathBuf, String> {
-    let app_dir = get_app_data_dir()?;
-    Ok(app_dir.join("weather_cache.json"))
+    let cache_dir = get_app_cache_dir()?;
+    Ok(cache_dir.join("weather_cache.json"))
 }
 
 pub fn get_location_key(location: &str) -> String {
     // Normalize location for caching (convert to lowercase, trim spaces)
     location.to_lowercase().trim().to_string()
 }
-
@@ -1,12 +1,18 @@
 use crate::modules::utils::get_app_data_dir;
+use crate::modules::wallpaper_transition::apply_wallpaper;
 use std::fs;
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
-use wallpaper;
 
 #[tauri::command]
 pub async fn set_wallpaper(path: String) -> Result<String, String> {
-    match wallpaper::set_from_path(&path) {
+    // Backends shell out and transitions take a while, so keep it off the async runtime
+    let wallpaper_path = path.clone();
+    let result = tokio::task::spawn_blocking(move || apply_wallpaper(Path::new(&wallpaper_path)))
+        .await
+        .map_err(|e| format!("Failed to set wallpaper: {}", e))?;
+
+    match result {
         Ok(_) => Ok(format!("Wallpaper set successfully: {}", path)),
         Err(e) => Err(format!("Failed to set wallpaper: {}", e)),
     }
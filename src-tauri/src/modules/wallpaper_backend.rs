@@ -0,0 +1,673 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use serde::Serialize;
+use crate::modules::settings::load_app_settings;
+use crate::modules::wallpaper_transition::{Transition, TransitionKind};
+
+// Value of the `wallpaper_backend` setting that picks a backend for the running session
+pub const AUTO_BACKEND: &str = "auto";
+
+/// A way of setting the desktop wallpaper. Most are thin wrappers around a desktop's own
+/// command line tool, looked up on PATH.
+pub trait WallpaperBackend: Send + Sync {
+    /// Stable identifier, stored in settings
+    fn id(&self) -> &'static str;
+    fn name(&self) -> &'static str;
+    /// Whether the backend's tools are installed and it can work in the current session
+    fn is_available(&self) -> bool;
+    fn set(&self, path: &Path) -> Result<(), String>;
+
+    /// The image currently shown, where the backend can tell; `None` if unknown
+    fn current(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Whether the backend animates wallpaper changes itself
+    fn supports_transitions(&self) -> bool {
+        false
+    }
+
+    fn set_with_transition(&self, path: &Path, _transition: &Transition) -> Result<(), String> {
+        self.set(path)
+    }
+
+    /// Whether the desktop behind the backend has its own lock screen image
+    fn supports_lock_screen(&self) -> bool {
+        false
+    }
+
+    fn set_lock_screen(&self, _path: &Path) -> Result<(), String> {
+        Err(format!("{} has no lock screen image", self.name()))
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WallpaperBackendInfo {
+    pub id: String,
+    pub name: String,
+    pub available: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WallpaperBackendStatus {
+    pub detected: String, // What "auto" resolves to
+    pub active: String,   // What's used, after the override in settings
+    pub backends: Vec<WallpaperBackendInfo>,
+}
+
+fn find_executable(name: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+pub fn has_executable(name: &str) -> bool {
+    find_executable(name).is_some()
+}
+
+// XDG_CURRENT_DESKTOP is a colon-separated list, e.g. "ubuntu:GNOME"
+fn desktop_is(names: &[&str]) -> bool {
+    env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .any(|desktop| names.iter().any(|name| desktop.eq_ignore_ascii_case(name)))
+}
+
+pub fn is_wayland() -> bool {
+    env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+fn is_x11() -> bool {
+    env::var_os("DISPLAY").is_some() && !is_wayland()
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} failed ({}): {}", program, output.status, stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn path_str(path: &Path) -> Result<&str, String> {
+    path.to_str().ok_or(format!("Unsupported wallpaper path: {}", path.display()))
+}
+
+// gsettings wants a file:// URI; escape everything that isn't safe in a URI path
+fn file_uri(path: &Path) -> Result<String, String> {
+    let mut uri = String::from("file://");
+    for byte in path_str(path)?.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    Ok(uri)
+}
+
+fn path_from_uri(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://").unwrap_or(uri).as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut index = 0;
+    while index < encoded.len() {
+        let escaped = (encoded[index] == b'%')
+            .then(|| encoded.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                bytes.push(byte);
+                index += 3;
+            }
+            None => {
+                bytes.push(encoded[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+/// The `wallpaper` crate, which covers Windows, macOS and the common Linux desktops.
+struct SystemBackend;
+
+impl WallpaperBackend for SystemBackend {
+    fn id(&self) -> &'static str { "system" }
+    fn name(&self) -> &'static str { "System default" }
+    fn is_available(&self) -> bool { true }
+
+    fn set(&self, path: &Path) -> Result<(), String> {
+        wallpaper::set_from_path(path_str(path)?).map_err(|e| e.to_string())
+    }
+
+    fn current(&self) -> Option<PathBuf> {
+        wallpaper::get().ok().filter(|path| !path.is_empty()).map(PathBuf::from)
+    }
+}
+
+struct GnomeBackend;
+
+impl WallpaperBackend for GnomeBackend {
+    fn id(&self) -> &'static str { "gnome" }
+    fn name(&self) -> &'static str { "GNOME" }
+
+    fn is_available(&self) -> bool {
+        desktop_is(&["GNOME", "Unity", "Budgie", "Pantheon"]) && has_executable("gsettings")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), String> {
+        let uri = file_uri(path)?;
+        run("gsettings", &["set", "org.gnome.desktop.background", "picture-uri", &uri])?;
+        // GNOME 42+ shows a separate image in dark mode; older versions don't have the key
+        if let Err(e) = run("gsettings", &["set", "org.gnome.desktop.background", "picture-uri-dark", &uri]) {
+            println!("Not setting dark mode wallpaper: {}", e);
+        }
+        Ok(())
+    }
+
+    fn current(&self) -> Option<PathBuf> {
+        // Printed as a GVariant string, e.g. 'file:///home/me/rain.jpg'
+        let output = run("gsettings", &["get", "org.gnome.desktop.background", "picture-uri"]).ok()?;
+        path_from_uri(output.trim().trim_matches('\''))
+    }
+
+    fn supports_lock_screen(&self) -> bool {
+        true
+    }
+
+    fn set_lock_screen(&self, path: &Path) -> Result<(), String> {
+        run("gsettings", &["set", "org.gnome.desktop.screensaver", "picture-uri", &file_uri(path)?]).map(|_| ())
+    }
+}
+
+struct KdeBackend;
+
+impl WallpaperBackend for KdeBackend {
+    fn id(&self) -> &'static str { "kde" }
+    fn name(&self) -> &'static str { "KDE Plasma" }
+
+    fn is_available(&self) -> bool {
+        desktop_is(&["KDE"]) && has_executable("plasma-apply-wallpaperimage")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), String> {
+        run("plasma-apply-wallpaperimage", &[path_str(path)?]).map(|_| ())
+    }
+
+    fn supports_lock_screen(&self) -> bool {
+        true
+    }
+
+    fn set_lock_screen(&self, path: &Path) -> Result<(), String> {
+        // Plasma 6 ships kwriteconfig6, Plasma 5 kwriteconfig5
+        let kwriteconfig = ["kwriteconfig6", "kwriteconfig5"]
+            .into_iter()
+            .find(|name| has_executable(name))
+            .ok_or("kwriteconfig not found")?;
+        run(kwriteconfig, &[
+            "--file", "kscreenlockerrc",
+            "--group", "Greeter", "--group", "Wallpaper", "--group", "org.kde.image", "--group", "General",
+            "--key", "Image", &file_uri(path)?,
+        ]).map(|_| ())
+    }
+}
+
+struct XfceBackend;
+
+impl WallpaperBackend for XfceBackend {
+    fn id(&self) -> &'static str { "xfce" }
+    fn name(&self) -> &'static str { "XFCE" }
+
+    fn is_available(&self) -> bool {
+        desktop_is(&["XFCE"]) && has_executable("xfconf-query")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), String> {
+        // Each monitor and workspace has its own property, e.g.
+        // /backdrop/screen0/monitoreDP-1/workspace0/last-image
+        let properties = run("xfconf-query", &["-c", "xfce4-desktop", "-l"])?;
+        let image_properties: Vec<&str> = properties
+            .lines()
+            .map(str::trim)
+            .filter(|property| property.ends_with("/last-image"))
+            .collect();
+        if image_properties.is_empty() {
+            return Err("No XFCE backdrop properties found".to_string());
+        }
+
+        for property in image_properties {
+            run("xfconf-query", &["-c", "xfce4-desktop", "-p", property, "-s", path_str(path)?])?;
+        }
+        Ok(())
+    }
+
+    fn current(&self) -> Option<PathBuf> {
+        // The app sets every monitor to the same image, so the first one is representative
+        let properties = run("xfconf-query", &["-c", "xfce4-desktop", "-l"]).ok()?;
+        let property = properties.lines().map(str::trim).find(|property| property.ends_with("/last-image"))?;
+        let output = run("xfconf-query", &["-c", "xfce4-desktop", "-p", property]).ok()?;
+        Some(PathBuf::from(output.trim()))
+    }
+}
+
+struct SwwwBackend;
+
+impl WallpaperBackend for SwwwBackend {
+    fn id(&self) -> &'static str { "swww" }
+    fn name(&self) -> &'static str { "swww" }
+
+    fn is_available(&self) -> bool {
+        // swww only works with its daemon running
+        is_wayland() && has_executable("swww") && run("swww", &["query"]).is_ok()
+    }
+
+    fn set(&self, path: &Path) -> Result<(), String> {
+        run("swww", &["img", path_str(path)?, "--transition-type", "none"]).map(|_| ())
+    }
+
+    fn current(&self) -> Option<PathBuf> {
+        // One line per output, e.g. "eDP-1: 1920x1080, scale: 1, currently displaying: image: /path"
+        let output = run("swww", &["query"]).ok()?;
+        let line = output.lines().next()?;
+        let (_, image) = line.split_once("image: ")?;
+        Some(PathBuf::from(image.trim()))
+    }
+
+    fn supports_transitions(&self) -> bool {
+        true
+    }
+
+    fn set_with_transition(&self, path: &Path, transition: &Transition) -> Result<(), String> {
+        let transition_type = match transition.kind {
+            TransitionKind::Crossfade => "fade",
+            TransitionKind::Wipe => "wipe",
+            TransitionKind::Grow => "grow",
+        };
+        let duration = format!("{:.2}", transition.duration.as_secs_f32());
+        run("swww", &[
+            "img", path_str(path)?,
+            "--transition-type", transition_type,
+            "--transition-duration", &duration,
+        ]).map(|_| ())
+    }
+}
+
+// swaybg draws the wallpaper for as long as it runs, so the app keeps it alive
+static SWAYBG_PROCESS: Mutex<Option<Child>> = Mutex::new(None);
+
+struct SwaybgBackend;
+
+impl WallpaperBackend for SwaybgBackend {
+    fn id(&self) -> &'static str { "swaybg" }
+    fn name(&self) -> &'static str { "swaybg" }
+
+    fn is_available(&self) -> bool {
+        is_wayland() && has_executable("swaybg")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), String> {
+        let child = Command::new("swaybg")
+            .args(["--image", path_str(path)?, "--mode", "fill"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to run swaybg: {}", e))?;
+
+        // Stop the previous instance only once the new one is up, to avoid a blank frame
+        if let Some(mut previous) = SWAYBG_PROCESS.lock().unwrap().replace(child) {
+            let _ = previous.kill();
+            let _ = previous.wait();
+        }
+        Ok(())
+    }
+}
+
+struct HyprpaperBackend;
+
+impl WallpaperBackend for HyprpaperBackend {
+    fn id(&self) -> &'static str { "hyprpaper" }
+    fn name(&self) -> &'static str { "hyprpaper" }
+
+    fn is_available(&self) -> bool {
+        env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some()
+            && has_executable("hyprctl")
+            && has_executable("hyprpaper")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), String> {
+        let path = path_str(path)?;
+        // hyprpaper only shows preloaded images; an empty monitor name means all monitors
+        run("hyprctl", &["hyprpaper", "preload", path])?;
+        run("hyprctl", &["hyprpaper", "wallpaper", &format!(",{}", path)])?;
+        if let Err(e) = run("hyprctl", &["hyprpaper", "unload", "unused"]) {
+            eprintln!("Warning: Failed to unload previous wallpapers: {}", e);
+        }
+        Ok(())
+    }
+
+    fn current(&self) -> Option<PathBuf> {
+        // One line per monitor, e.g. "eDP-1 = /path"
+        let output = run("hyprctl", &["hyprpaper", "listactive"]).ok()?;
+        let (_, image) = output.lines().next()?.split_once(" = ")?;
+        Some(PathBuf::from(image.trim()))
+    }
+}
+
+struct FehBackend;
+
+impl WallpaperBackend for FehBackend {
+    fn id(&self) -> &'static str { "feh" }
+    fn name(&self) -> &'static str { "feh" }
+
+    fn is_available(&self) -> bool {
+        is_x11() && has_executable("feh")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), String> {
+        run("feh", &["--no-fehbg", "--bg-fill", path_str(path)?]).map(|_| ())
+    }
+}
+
+struct XwallpaperBackend;
+
+impl WallpaperBackend for XwallpaperBackend {
+    fn id(&self) -> &'static str { "xwallpaper" }
+    fn name(&self) -> &'static str { "xwallpaper" }
+
+    fn is_available(&self) -> bool {
+        is_x11() && has_executable("xwallpaper")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), String> {
+        run("xwallpaper", &["--zoom", path_str(path)?]).map(|_| ())
+    }
+}
+
+// In detection order: full desktops first, then Wayland compositor tools, then X11 tools
+static BACKENDS: [&dyn WallpaperBackend; 9] = [
+    &GnomeBackend,
+    &KdeBackend,
+    &XfceBackend,
+    &HyprpaperBackend,
+    &SwwwBackend,
+    &SwaybgBackend,
+    &FehBackend,
+    &XwallpaperBackend,
+    &SystemBackend,
+];
+
+fn find_backend(id: &str) -> Option<&'static dyn WallpaperBackend> {
+    BACKENDS.iter().copied().find(|backend| backend.id() == id)
+}
+
+/// The first backend that works in the current session; always the `wallpaper` crate
+/// outside Linux.
+pub fn detect_backend() -> &'static dyn WallpaperBackend {
+    if !cfg!(target_os = "linux") {
+        return &SystemBackend;
+    }
+    BACKENDS
+        .iter()
+        .copied()
+        .find(|backend| backend.is_available())
+        .unwrap_or(&SystemBackend)
+}
+
+/// The backend chosen in settings, or the detected one when set to "auto".
+pub fn active_backend() -> &'static dyn WallpaperBackend {
+    let configured = load_app_settings().wallpaper_backend;
+    if configured == AUTO_BACKEND {
+        return detect_backend();
+    }
+
+    match find_backend(&configured) {
+        Some(backend) => {
+            if !backend.is_available() {
+                eprintln!("Warning: Wallpaper backend {} doesn't look available, using it anyway", backend.id());
+            }
+            backend
+        }
+        None => {
+            eprintln!("Warning: Unknown wallpaper backend {}, detecting one instead", configured);
+            detect_backend()
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_wallpaper_backends() -> Result<WallpaperBackendStatus, String> {
+    Ok(WallpaperBackendStatus {
+        detected: detect_backend().id().to_string(),
+        active: active_backend().id().to_string(),
+        backends: BACKENDS
+            .iter()
+            .map(|backend| WallpaperBackendInfo {
+                id: backend.id().to_string(),
+                name: backend.name().to_string(),
+                available: backend.is_available(),
+            })
+            .collect(),
+    })
+}
+
+// The stubs are shell scripts
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::Duration;
+
+    // PATH is process-wide, so tests that swap it can't run alongside each other
+    static PATH_LOCK: Mutex<()> = Mutex::new(());
+
+    const WALLPAPER: &str = "/home/me/My Pictures/rain #1.jpg";
+
+    /// Stand-ins for the desktop tools, first on PATH for as long as this lives. Each stub
+    /// records its arguments and prints whatever `output` was given for it.
+    struct StubTools {
+        dir: tempfile::TempDir,
+        original_path: Option<std::ffi::OsString>,
+        _guard: std::sync::MutexGuard<'static, ()>,
+    }
+
+    impl StubTools {
+        fn new(names: &[&str]) -> Self {
+            let guard = PATH_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            let dir = tempfile::tempdir().unwrap();
+            for name in names {
+                let script = format!(
+                    "#!/bin/sh\nfor arg in \"$@\"; do printf '%s\\n' \"$arg\"; done >> '{dir}/{name}.log'\n\
+                     echo --- >> '{dir}/{name}.log'\n[ -f '{dir}/{name}.out' ] && cat '{dir}/{name}.out'\nexit 0\n",
+                    dir = dir.path().display(),
+                    name = name,
+                );
+                let stub = dir.path().join(name);
+                fs::write(&stub, script).unwrap();
+                fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+            }
+
+            let original_path = env::var_os("PATH");
+            let mut paths = vec![dir.path().to_path_buf()];
+            paths.extend(original_path.iter().flat_map(env::split_paths));
+            env::set_var("PATH", env::join_paths(paths).unwrap());
+
+            StubTools { dir, original_path, _guard: guard }
+        }
+
+        fn output(&self, name: &str, output: &str) {
+            fs::write(self.dir.path().join(format!("{}.out", name)), output).unwrap();
+        }
+
+        /// Arguments of every call to `name`, in order
+        fn calls(&self, name: &str) -> Vec<Vec<String>> {
+            let log = fs::read_to_string(self.dir.path().join(format!("{}.log", name))).unwrap_or_default();
+            log.split_terminator("---\n")
+                .map(|call| call.lines().map(str::to_string).collect())
+                .collect()
+        }
+    }
+
+    impl Drop for StubTools {
+        fn drop(&mut self) {
+            match &self.original_path {
+                Some(path) => env::set_var("PATH", path),
+                None => env::remove_var("PATH"),
+            }
+        }
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn file_uri_escapes_unsafe_characters() {
+        assert_eq!(
+            file_uri(Path::new(WALLPAPER)).unwrap(),
+            "file:///home/me/My%20Pictures/rain%20%231.jpg"
+        );
+        assert_eq!(file_uri(Path::new("/tmp/caf\u{e9}.png")).unwrap(), "file:///tmp/caf%C3%A9.png");
+    }
+
+    #[test]
+    fn path_from_uri_decodes_escapes() {
+        assert_eq!(path_from_uri("file:///home/me/My%20Pictures/rain%20%231.jpg").unwrap(), PathBuf::from(WALLPAPER));
+        assert_eq!(path_from_uri("file:///tmp/caf%C3%A9.png").unwrap(), PathBuf::from("/tmp/caf\u{e9}.png"));
+        // Malformed escapes are kept as they are
+        assert_eq!(path_from_uri("file:///tmp/100%.png").unwrap(), PathBuf::from("/tmp/100%.png"));
+        assert_eq!(path_from_uri("file:///tmp/%zz.png").unwrap(), PathBuf::from("/tmp/%zz.png"));
+
+        for path in [WALLPAPER, "/tmp/caf\u{e9}.png", "/tmp/a%b.png"] {
+            assert_eq!(path_from_uri(&file_uri(Path::new(path)).unwrap()).unwrap(), PathBuf::from(path));
+        }
+    }
+
+    #[test]
+    fn gnome_sets_light_and_dark_picture() {
+        let stubs = StubTools::new(&["gsettings"]);
+        GnomeBackend.set(Path::new(WALLPAPER)).unwrap();
+
+        let uri = "file:///home/me/My%20Pictures/rain%20%231.jpg";
+        assert_eq!(stubs.calls("gsettings"), vec![
+            args(&["set", "org.gnome.desktop.background", "picture-uri", uri]),
+            args(&["set", "org.gnome.desktop.background", "picture-uri-dark", uri]),
+        ]);
+    }
+
+    #[test]
+    fn gnome_sets_lock_screen_picture() {
+        let stubs = StubTools::new(&["gsettings"]);
+        GnomeBackend.set_lock_screen(Path::new("/tmp/lock.png")).unwrap();
+        assert_eq!(stubs.calls("gsettings"), vec![
+            args(&["set", "org.gnome.desktop.screensaver", "picture-uri", "file:///tmp/lock.png"]),
+        ]);
+    }
+
+    #[test]
+    fn gnome_current_parses_gvariant_string() {
+        let stubs = StubTools::new(&["gsettings"]);
+        stubs.output("gsettings", "'file:///home/me/My%20Pictures/rain%20%231.jpg'\n");
+
+        assert_eq!(GnomeBackend.current(), Some(PathBuf::from(WALLPAPER)));
+        assert_eq!(stubs.calls("gsettings"), vec![
+            args(&["get", "org.gnome.desktop.background", "picture-uri"]),
+        ]);
+    }
+
+    #[test]
+    fn kde_passes_path_to_plasma() {
+        let stubs = StubTools::new(&["plasma-apply-wallpaperimage"]);
+        KdeBackend.set(Path::new(WALLPAPER)).unwrap();
+        assert_eq!(stubs.calls("plasma-apply-wallpaperimage"), vec![args(&[WALLPAPER])]);
+    }
+
+    #[test]
+    fn xfce_sets_every_backdrop() {
+        let stubs = StubTools::new(&["xfconf-query"]);
+        stubs.output("xfconf-query", concat!(
+            "/backdrop/screen0/monitoreDP-1/workspace0/color-style\n",
+            "/backdrop/screen0/monitoreDP-1/workspace0/last-image\n",
+            "/backdrop/screen0/monitorHDMI-1/workspace0/last-image\n",
+        ));
+        XfceBackend.set(Path::new(WALLPAPER)).unwrap();
+
+        assert_eq!(stubs.calls("xfconf-query"), vec![
+            args(&["-c", "xfce4-desktop", "-l"]),
+            args(&["-c", "xfce4-desktop", "-p", "/backdrop/screen0/monitoreDP-1/workspace0/last-image", "-s", WALLPAPER]),
+            args(&["-c", "xfce4-desktop", "-p", "/backdrop/screen0/monitorHDMI-1/workspace0/last-image", "-s", WALLPAPER]),
+        ]);
+    }
+
+    #[test]
+    fn xfce_without_backdrops_fails() {
+        let stubs = StubTools::new(&["xfconf-query"]);
+        assert!(XfceBackend.set(Path::new(WALLPAPER)).is_err());
+        assert_eq!(stubs.calls("xfconf-query").len(), 1);
+    }
+
+    #[test]
+    fn swww_sets_with_and_without_transition() {
+        let stubs = StubTools::new(&["swww"]);
+        SwwwBackend.set(Path::new(WALLPAPER)).unwrap();
+        let transition = Transition { kind: TransitionKind::Wipe, duration: Duration::from_millis(1500) };
+        SwwwBackend.set_with_transition(Path::new(WALLPAPER), &transition).unwrap();
+
+        assert_eq!(stubs.calls("swww"), vec![
+            args(&["img", WALLPAPER, "--transition-type", "none"]),
+            args(&["img", WALLPAPER, "--transition-type", "wipe", "--transition-duration", "1.50"]),
+        ]);
+    }
+
+    #[test]
+    fn swww_current_parses_query_output() {
+        let stubs = StubTools::new(&["swww"]);
+        stubs.output("swww", &format!(
+            "eDP-1: 1920x1080, scale: 1, currently displaying: image: {}\n\
+             HDMI-A-1: 2560x1440, scale: 1, currently displaying: image: /tmp/other.jpg\n",
+            WALLPAPER,
+        ));
+
+        assert_eq!(SwwwBackend.current(), Some(PathBuf::from(WALLPAPER)));
+        assert_eq!(stubs.calls("swww"), vec![args(&["query"])]);
+
+        stubs.output("swww", "eDP-1: 1920x1080, scale: 1, currently displaying: color: 000000\n");
+        assert_eq!(SwwwBackend.current(), None);
+    }
+
+    #[test]
+    fn hyprpaper_preloads_sets_and_unloads() {
+        let stubs = StubTools::new(&["hyprctl"]);
+        HyprpaperBackend.set(Path::new(WALLPAPER)).unwrap();
+
+        assert_eq!(stubs.calls("hyprctl"), vec![
+            args(&["hyprpaper", "preload", WALLPAPER]),
+            args(&["hyprpaper", "wallpaper", &format!(",{}", WALLPAPER)]),
+            args(&["hyprpaper", "unload", "unused"]),
+        ]);
+    }
+
+    #[test]
+    fn hyprpaper_current_parses_listactive() {
+        let stubs = StubTools::new(&["hyprctl"]);
+        stubs.output("hyprctl", &format!("eDP-1 = {}\n", WALLPAPER));
+        assert_eq!(HyprpaperBackend.current(), Some(PathBuf::from(WALLPAPER)));
+        assert_eq!(stubs.calls("hyprctl"), vec![args(&["hyprpaper", "listactive"])]);
+    }
+
+    #[test]
+    fn feh_fills_without_writing_fehbg() {
+        let stubs = StubTools::new(&["feh"]);
+        FehBackend.set(Path::new(WALLPAPER)).unwrap();
+        assert_eq!(stubs.calls("feh"), vec![args(&["--no-fehbg", "--bg-fill", WALLPAPER])]);
+    }
+}
@@ -0,0 +1,281 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, RgbImage};
+use crate::modules::settings::load_app_settings;
+use crate::modules::utils::get_app_cache_dir;
+use crate::modules::wallpaper_backend::{active_backend, WallpaperBackend};
+
+const FRAMES_DIR: &str = "transition_frames";
+
+// Backends without their own animation are shown a few blended frames. Each frame is a
+// full wallpaper change, so more than a handful just makes slow desktops stutter.
+const FRAME_COUNT: u32 = 6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TransitionKind {
+    Crossfade,
+    Wipe, // The new image slides in from the left
+    Grow, // The new image grows out of the center as a circle
+}
+
+impl TransitionKind {
+    fn from_setting(value: &str) -> Option<Self> {
+        match value {
+            "crossfade" => Some(Self::Crossfade),
+            "wipe" => Some(Self::Wipe),
+            "grow" => Some(Self::Grow),
+            _ => None, // "none"
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Transition {
+    pub kind: TransitionKind,
+    pub duration: Duration,
+}
+
+// The wallpaper the app set last, which a transition starts from
+static CURRENT_WALLPAPER: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+// Only one transition plays at a time; a later change waits for the running one to finish
+static TRANSITION_LOCK: Mutex<()> = Mutex::new(());
+
+fn configured_transition() -> Option<Transition> {
+    let settings = load_app_settings();
+    let kind = TransitionKind::from_setting(&settings.wallpaper_transition)?;
+    if settings.wallpaper_transition_duration_ms == 0 {
+        return None;
+    }
+    Some(Transition {
+        kind,
+        duration: Duration::from_millis(settings.wallpaper_transition_duration_ms),
+    })
+}
+
+// Value of the new image's pixel at (x, y) for the given progress, between 0 and 1
+fn blend_weight(kind: TransitionKind, progress: f32, x: u32, y: u32, width: u32, height: u32) -> f32 {
+    match kind {
+        TransitionKind::Crossfade => progress,
+        TransitionKind::Wipe => {
+            if (x as f32) < progress * width as f32 { 1.0 } else { 0.0 }
+        }
+        TransitionKind::Grow => {
+            let (dx, dy) = (x as f32 - width as f32 / 2.0, y as f32 - height as f32 / 2.0);
+            let max_radius = (width as f32).hypot(height as f32) / 2.0;
+            if (dx * dx + dy * dy).sqrt() < progress * max_radius { 1.0 } else { 0.0 }
+        }
+    }
+}
+
+fn render_frame(from: &RgbImage, to: &RgbImage, kind: TransitionKind, progress: f32) -> RgbImage {
+    let (width, height) = to.dimensions();
+    RgbImage::from_fn(width, height, |x, y| {
+        let weight = blend_weight(kind, progress, x, y, width, height);
+        let (a, b) = (from.get_pixel(x, y), to.get_pixel(x, y));
+        image::Rgb(std::array::from_fn(|i| {
+            (a[i] as f32 * (1.0 - weight) + b[i] as f32 * weight).round() as u8
+        }))
+    })
+}
+
+fn load_rgb(path: &Path) -> Result<RgbImage, String> {
+    image::open(path)
+        .map(|image| image.to_rgb8())
+        .map_err(|e| format!("Failed to load {}: {}", path.display(), e))
+}
+
+/// Renders the intermediate frames between two wallpapers into the cache directory.
+/// Frames have the new image's size; the old one is scaled to match.
+fn render_frames(from: &Path, to: &Path, kind: TransitionKind) -> Result<Vec<PathBuf>, String> {
+    let target = load_rgb(to)?;
+    let (width, height) = target.dimensions();
+    let source = imageops::resize(&load_rgb(from)?, width, height, FilterType::Triangle);
+
+    // Frames of an interrupted transition are no longer on screen
+    remove_frames();
+    let frames_dir = get_app_cache_dir()?.join(FRAMES_DIR);
+    fs::create_dir_all(&frames_dir)
+        .map_err(|e| format!("Failed to create transition frames directory: {}", e))?;
+
+    // Unique names, since some desktops don't reload a wallpaper whose path didn't change
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+
+    (1..=FRAME_COUNT)
+        .map(|index| {
+            let progress = index as f32 / (FRAME_COUNT + 1) as f32;
+            let frame_path = frames_dir.join(format!("frame_{}_{}.jpg", stamp, index));
+            DynamicImage::ImageRgb8(render_frame(&source, &target, kind, progress))
+                .save(&frame_path)
+                .map_err(|e| format!("Failed to write transition frame: {}", e))?;
+            Ok(frame_path)
+        })
+        .collect()
+}
+
+fn remove_frames() {
+    if let Ok(frames_dir) = get_app_cache_dir().map(|dir| dir.join(FRAMES_DIR)) {
+        if frames_dir.exists() {
+            let _ = fs::remove_dir_all(&frames_dir);
+        }
+    }
+}
+
+fn play_frames(backend: &dyn WallpaperBackend, from: &Path, to: &Path, transition: &Transition) -> Result<(), String> {
+    // Render everything up front so the frames are evenly spaced
+    let frames = render_frames(from, to, transition.kind)?;
+    let interval = transition.duration / (FRAME_COUNT + 1);
+
+    for frame in &frames {
+        backend.set(frame)?;
+        thread::sleep(interval);
+    }
+    Ok(())
+}
+
+// Shows the blended frames, then the new wallpaper itself. The frames are only needed
+// until the final image is up.
+fn play_transition(backend: &dyn WallpaperBackend, from: &Path, to: &Path, transition: &Transition) -> Result<(), String> {
+    if let Err(e) = play_frames(backend, from, to, transition) {
+        eprintln!("Warning: Skipping wallpaper transition: {}", e);
+    }
+    let result = backend.set(to);
+    remove_frames();
+    result
+}
+
+// The wallpaper a transition starts from. After a restart that's whatever the desktop
+// shows, so the first change animates too.
+fn previous_wallpaper(backend: &dyn WallpaperBackend) -> Option<PathBuf> {
+    let mut current = CURRENT_WALLPAPER.lock().unwrap();
+    if current.is_none() {
+        *current = backend.current();
+    }
+    current.clone()
+}
+
+/// Sets the wallpaper through the active backend, animating from the previous one if a
+/// transition is configured. Blocks for the length of the transition.
+pub fn apply_wallpaper(path: &Path) -> Result<(), String> {
+    let _transition_guard = TRANSITION_LOCK.lock().unwrap();
+    let backend = active_backend();
+
+    let result = match configured_transition() {
+        Some(transition) if backend.supports_transitions() => backend.set_with_transition(path, &transition),
+        Some(transition) => {
+            // Without a known previous image there's nothing to animate from
+            match previous_wallpaper(backend).filter(|previous| previous != path && previous.exists()) {
+                Some(previous) => play_transition(backend, &previous, path, &transition),
+                None => backend.set(path),
+            }
+        }
+        None => backend.set(path),
+    };
+    result.map_err(|e| format!("{}: {}", backend.name(), e))?;
+
+    *CURRENT_WALLPAPER.lock().unwrap() = Some(path.to_path_buf());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::utils::use_test_data_dir;
+
+    // Records every image it's asked to show, and whether the file was there at the time
+    struct RecordingBackend {
+        shown: Mutex<Vec<(PathBuf, bool)>>,
+        on_screen: Option<PathBuf>,
+    }
+
+    impl RecordingBackend {
+        fn new(on_screen: Option<PathBuf>) -> Self {
+            RecordingBackend { shown: Mutex::new(Vec::new()), on_screen }
+        }
+    }
+
+    impl WallpaperBackend for RecordingBackend {
+        fn id(&self) -> &'static str { "recording" }
+        fn name(&self) -> &'static str { "Recording" }
+        fn is_available(&self) -> bool { true }
+
+        fn set(&self, path: &Path) -> Result<(), String> {
+            self.shown.lock().unwrap().push((path.to_path_buf(), path.exists()));
+            Ok(())
+        }
+
+        fn current(&self) -> Option<PathBuf> {
+            self.on_screen.clone()
+        }
+    }
+
+    fn write_image(path: &Path, width: u32, height: u32, color: [u8; 3]) {
+        RgbImage::from_pixel(width, height, image::Rgb(color)).save(path).unwrap();
+    }
+
+    #[test]
+    fn transition_shows_blended_frames_then_removes_them() {
+        let test_dir = use_test_data_dir();
+        let (from, to) = (test_dir.path().join("from.png"), test_dir.path().join("to.png"));
+        write_image(&from, 4, 4, [0, 0, 0]);
+        write_image(&to, 8, 6, [255, 255, 255]);
+
+        let backend = RecordingBackend::new(None);
+        let transition = Transition { kind: TransitionKind::Crossfade, duration: Duration::from_millis(7) };
+        play_transition(&backend, &from, &to, &transition).unwrap();
+
+        let shown = backend.shown.lock().unwrap();
+        assert_eq!(shown.len(), FRAME_COUNT as usize + 1);
+        assert_eq!(shown.last().unwrap().0, to);
+
+        let frames = &shown[..FRAME_COUNT as usize];
+        assert!(frames.iter().all(|(frame, existed)| *existed && frame.starts_with(get_app_cache_dir().unwrap())));
+
+        assert!(frames.iter().all(|(frame, _)| !frame.exists()));
+        assert!(!get_app_cache_dir().unwrap().join(FRAMES_DIR).exists());
+    }
+
+    #[test]
+    fn rendered_frames_match_target_size_and_progress() {
+        let test_dir = use_test_data_dir();
+        let (from, to) = (test_dir.path().join("from.png"), test_dir.path().join("to.png"));
+        write_image(&from, 4, 4, [0, 0, 0]);
+        write_image(&to, 8, 6, [255, 255, 255]);
+
+        let frames = render_frames(&from, &to, TransitionKind::Crossfade).unwrap();
+        assert_eq!(frames.len(), FRAME_COUNT as usize);
+
+        let brightness: Vec<u8> = frames
+            .iter()
+            .map(|frame| {
+                let image = load_rgb(frame).unwrap();
+                assert_eq!(image.dimensions(), (8, 6));
+                image.get_pixel(4, 3)[0]
+            })
+            .collect();
+        assert!(brightness.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", brightness);
+
+        remove_frames();
+        assert!(frames.iter().all(|frame| !frame.exists()));
+    }
+
+    #[test]
+    fn first_transition_starts_from_the_desktop_wallpaper() {
+        let test_dir = use_test_data_dir();
+        let on_screen = test_dir.path().join("desktop.png");
+        *CURRENT_WALLPAPER.lock().unwrap() = None;
+
+        let backend = RecordingBackend::new(Some(on_screen.clone()));
+        assert_eq!(previous_wallpaper(&backend), Some(on_screen.clone()));
+
+        // Once the app has set a wallpaper, that's what it starts from
+        let set_by_app = test_dir.path().join("rain.png");
+        *CURRENT_WALLPAPER.lock().unwrap() = Some(set_by_app.clone());
+        assert_eq!(previous_wallpaper(&backend), Some(set_by_app));
+        *CURRENT_WALLPAPER.lock().unwrap() = None;
+    }
+}
@@ -1,47 +1,29 @@
 use std::fs;
-use chrono::{Utc};
+use chrono::{Local, Utc};
 use serde_json;
-use crate::modules::types::{WeatherApiResponse, WeatherCache, CurrentConditions, TimePeriodsResponse};
+use crate::modules::types::{WeatherApiResponse, CurrentConditions, TimePeriodsResponse, WeatherCacheEntryInfo};
+use crate::modules::persistence::lock_file;
 use crate::modules::utils::{get_cache_file_path, get_location_key};
+use crate::modules::air_quality::{get_air_quality_categories, max_pollen_count};
+use crate::modules::moon::{get_moon_categories, moon_illumination_for, moon_phase_for};
+use crate::modules::alerts::{active_alerts_at, get_alert_categories};
+use crate::modules::weather_cache::{get_cached_weather, store_cached_weather, list_cache_entries, remove_cache_entry};
 use crate::modules::time::{get_time_period_with_sun, calculate_time_periods};
 use crate::modules::settings::load_app_settings;
-
-fn load_weather_cache() -> Option<WeatherCache> {
-    let cache_path = get_cache_file_path().ok()?;
-    let cache_content = fs::read_to_string(cache_path).ok()?;
-    serde_json::from_str(&cache_content).ok()
-}
-
-fn save_weather_cache(cache: &WeatherCache) -> Result<(), String> {
-    let cache_path = get_cache_file_path()?;
-    let cache_content = serde_json::to_string_pretty(cache)
-        .map_err(|e| format!("Failed to serialize cache: {}", e))?;
-    
-    fs::write(cache_path, cache_content)
-        .map_err(|e| format!("Failed to write cache: {}", e))?;
-    
-    Ok(())
-}
-
-fn is_cache_valid(cache: &WeatherCache, current_location: &str, cache_duration_minutes: u64) -> bool {
-    let now = Utc::now().timestamp();
-    let cache_age = now - cache.cached_at;
-    let cache_duration_seconds = (cache_duration_minutes * 60) as i64;
-    
-    // Check if cache is within valid time window and location matches
-    cache_age < cache_duration_seconds && cache.location_key == current_location
-}
+use crate::modules::location::resolve_location_query;
+use crate::modules::geolocation::refresh_auto_location;
+use crate::modules::secrets::redact_secrets;
 
 async fn fetch_fresh_weather_data(api_key: &str, location: &str) -> Result<WeatherApiResponse, String> {
-    // WeatherAPI.com endpoint - includes current weather and astronomy data
+    // WeatherAPI.com forecast endpoint - includes current weather plus hourly forecast for today and tomorrow
     let url = format!(
-        "https://api.weatherapi.com/v1/current.json?key={}&q={}&aqi=no", 
+        "https://api.weatherapi.com/v1/forecast.json?key={}&q={}&days=2&aqi=yes&pollen=yes&alerts=yes", 
         api_key, location
     );
     
     let response = reqwest::get(&url)
         .await
-        .map_err(|e| format!("HTTP error: {}", e))?;
+        .map_err(|e| redact_secrets(&format!("HTTP error: {}", e)))?;
     
     if !response.status().is_success() {
         return Err(format!("Weather API error: {}", response.status()));
@@ -55,15 +37,15 @@ async fn fetch_fresh_weather_data(api_key: &str, location: &str) -> Result<Weath
     
     let astronomy_response = reqwest::get(&astronomy_url)
         .await
-        .map_err(|e| format!("Astronomy API error: {}", e))?;
+        .map_err(|e| redact_secrets(&format!("Astronomy API error: {}", e)))?;
     
     let weather_data: serde_json::Value = response.json()
         .await
-        .map_err(|e| format!("JSON parse error: {}", e))?;
+        .map_err(|e| redact_secrets(&format!("JSON parse error: {}", e)))?;
     
     let astronomy_data: serde_json::Value = astronomy_response.json()
         .await
-        .map_err(|e| format!("Astronomy JSON parse error: {}", e))?;
+        .map_err(|e| redact_secrets(&format!("Astronomy JSON parse error: {}", e)))?;
     
     // Combine the responses
     let mut combined_response = weather_data;
@@ -77,51 +59,37 @@ async fn fetch_fresh_weather_data(api_key: &str, location: &str) -> Result<Weath
     Ok(final_response)
 }
 
-async fn get_weather_data() -> Result<WeatherApiResponse, String> {
+pub async fn get_weather_data() -> Result<WeatherApiResponse, String> {
     let settings = load_app_settings();
     
     if settings.weather_api_key.is_empty() {
         return Err("Weather API key not configured. Please set it in Settings.".to_string());
     }
     
+    // Resolve the device location natively (GeoClue) before falling back to auto:ip
+    refresh_auto_location(&settings).await;
+    let location = resolve_location_query(&settings)?;
     let api_key = settings.weather_api_key;
-    let location = if settings.use_auto_location {
-        "auto:ip".to_string()
-    } else {
-        if settings.location.is_empty() {
-            return Err("Location not configured. Please set it in Settings or enable auto-location.".to_string());
-        }
-        settings.location
-    };
     
     let location_key = get_location_key(&location);
     
     // Try to load from cache first
-    if let Some(cached_data) = load_weather_cache() {
-        if is_cache_valid(&cached_data, &location_key, settings.cache_duration_minutes) {
-            return Ok(cached_data.data);
-        }
+    if let Some(cached_data) = get_cached_weather(&location_key, settings.cache_duration_minutes) {
+        return Ok(cached_data);
     }
     
     // Cache miss or expired, fetch fresh data
     let fresh_data = fetch_fresh_weather_data(&api_key, &location).await?;
     
-    // Save to cache
-    let cache_entry = WeatherCache {
-        data: fresh_data.clone(),
-        cached_at: Utc::now().timestamp(),
-        location_key,
-    };
-    
     // Don't fail the entire operation if caching fails
-    if let Err(e) = save_weather_cache(&cache_entry) {
+    if let Err(e) = store_cached_weather(&location_key, &fresh_data, settings.cache_duration_minutes) {
         eprintln!("Warning: Failed to save weather cache: {}", e);
     }
     
     Ok(fresh_data)
 }
 
-fn get_weather_condition_category(condition_code: i32, condition_text: &str) -> Option<String> {
+pub fn get_weather_condition_category(condition_code: i32, condition_text: &str) -> Option<String> {
     // Map WeatherAPI condition codes to our categories
     // Reference: https://www.weatherapi.com/docs/weather_conditions.json
     match condition_code {
@@ -153,8 +121,38 @@ fn get_weather_condition_category(condition_code: i32, condition_text: &str) ->
     }
 }
 
+pub fn build_active_categories(
+    alert_categories: &[String],
+    weather_condition: &Option<String>,
+    environment_categories: &[String],
+    time_period: &str,
+) -> Vec<String> {
+    // Determine active categories based on priority
+    let mut active_categories = Vec::new();
+    
+    // Government alerts outrank everything else
+    active_categories.extend(alert_categories.iter().cloned());
+    
+    // Add weather condition if present (highest priority)
+    if let Some(ref condition) = weather_condition {
+        active_categories.push(condition.clone());
+    }
+    
+    // Air quality, pollen and moon indicators
+    active_categories.extend(environment_categories.iter().cloned());
+    
+    // Always add time period
+    active_categories.push(time_period.to_string());
+    
+    // Add fallback as lowest priority
+    active_categories.push("default".to_string());
+    
+    active_categories
+}
+
 #[tauri::command]
 pub async fn get_current_conditions() -> Result<CurrentConditions, String> {
+    let settings = load_app_settings();
     let weather_data = get_weather_data().await?;
     
     let weather_condition = get_weather_condition_category(
@@ -167,19 +165,18 @@ pub async fn get_current_conditions() -> Result<CurrentConditions, String> {
         Some(&weather_data.astronomy.astro.sunset)
     );
     
-    // Determine active categories based on priority
-    let mut active_categories = Vec::new();
-    
-    // Add weather condition if present (highest priority)
-    if let Some(ref condition) = weather_condition {
-        active_categories.push(condition.clone());
-    }
-    
-    // Always add time period
-    active_categories.push(time_period.clone());
-    
-    // Add fallback as lowest priority
-    active_categories.push("default".to_string());
+    let alerts = active_alerts_at(&weather_data, Utc::now().timestamp());
+    let air_quality = weather_data.current.air_quality.as_ref();
+    let pollen = weather_data.current.pollen.as_ref();
+    let now = Local::now();
+    let mut environment_categories = get_air_quality_categories(air_quality, pollen, &settings);
+    environment_categories.extend(get_moon_categories(&weather_data.astronomy.astro, &now, &time_period));
+    let active_categories = build_active_categories(
+        &get_alert_categories(&alerts),
+        &weather_condition,
+        &environment_categories,
+        &time_period,
+    );
     
     Ok(CurrentConditions {
         weather_condition,
@@ -190,6 +187,12 @@ pub async fn get_current_conditions() -> Result<CurrentConditions, String> {
         sunset: Some(weather_data.astronomy.astro.sunset.clone()),
         location: Some(format!("{}, {}", weather_data.location.name, weather_data.location.country)),
         active_categories,
+        alerts,
+        air_quality_index: air_quality.and_then(|aq| aq.us_epa_index),
+        pm2_5: air_quality.and_then(|aq| aq.pm2_5),
+        pollen_count: pollen.and_then(max_pollen_count),
+        moon_phase: Some(moon_phase_for(&weather_data.astronomy.astro, &now)),
+        moon_illumination: Some(moon_illumination_for(&weather_data.astronomy.astro, &now)),
     })
 }
 
@@ -210,8 +213,24 @@ pub async fn get_time_periods() -> Result<TimePeriodsResponse, String> {
 }
 
 #[tauri::command]
-pub async fn clear_weather_cache() -> Result<String, String> {
+pub async fn get_weather_cache_entries() -> Result<Vec<WeatherCacheEntryInfo>, String> {
+    let settings = load_app_settings();
+    Ok(list_cache_entries(settings.cache_duration_minutes))
+}
+
+#[tauri::command]
+pub async fn clear_weather_cache(location_key: Option<String>) -> Result<String, String> {
+    // Clear a single location when requested, otherwise drop the whole cache
+    if let Some(key) = location_key {
+        return if remove_cache_entry(&key)? {
+            Ok(format!("Weather cache cleared for {}", key))
+        } else {
+            Ok(format!("No cache entry found for {}", key))
+        };
+    }
+
     let cache_path = get_cache_file_path()?;
+    let _cache_lock = lock_file(&cache_path)?;
     
     if cache_path.exists() {
         fs::remove_file(cache_path)
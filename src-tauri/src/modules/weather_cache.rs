@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+use chrono::Utc;
+use serde_json;
+use crate::modules::types::{WeatherApiResponse, WeatherCache, WeatherCacheStore, WeatherCacheEntryInfo};
+use crate::modules::persistence::lock_file;
+use crate::modules::utils::get_cache_file_path;
+
+// Maximum number of locations kept in the cache before the least recently used one is evicted
+const MAX_CACHE_ENTRIES: usize = 8;
+
+// Access times from cache hits since the file was last written. Reads are frequent, so
+// they're only kept here and folded into the file by the next write.
+static ACCESS_TIMES: Mutex<Option<HashMap<String, i64>>> = Mutex::new(None);
+
+fn record_access(location_key: &str, now: i64) {
+    ACCESS_TIMES
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(location_key.to_string(), now);
+}
+
+// Brings the stored access times up to date with the ones only held in memory
+fn apply_access_times(store: &mut WeatherCacheStore) {
+    if let Some(access_times) = ACCESS_TIMES.lock().unwrap().as_ref() {
+        for (key, entry) in store.entries.iter_mut() {
+            if let Some(&accessed) = access_times.get(key) {
+                entry.last_accessed = entry.last_accessed.max(accessed);
+            }
+        }
+    }
+}
+
+fn load_cache_store() -> WeatherCacheStore {
+    let cache_path = match get_cache_file_path() {
+        Ok(path) => path,
+        Err(_) => return WeatherCacheStore::default(),
+    };
+
+    let cache_content = match fs::read_to_string(cache_path) {
+        Ok(content) => content,
+        Err(_) => return WeatherCacheStore::default(),
+    };
+
+    if let Ok(store) = serde_json::from_str::<WeatherCacheStore>(&cache_content) {
+        return store;
+    }
+
+    // Older versions stored a single entry; carry it over into the keyed store
+    let mut store = WeatherCacheStore::default();
+    if let Ok(mut legacy) = serde_json::from_str::<WeatherCache>(&cache_content) {
+        if legacy.last_accessed == 0 {
+            legacy.last_accessed = legacy.cached_at;
+        }
+        store.entries.insert(legacy.location_key.clone(), legacy);
+    }
+    store
+}
+
+// Loads the store, applies `update` and writes it back if it returns true, all under the
+// cache file's lock so the scheduler and UI-triggered fetches don't drop each other's entries
+fn update_cache_store<R>(update: impl FnOnce(&mut WeatherCacheStore) -> (R, bool)) -> Result<R, String> {
+    let cache_lock = lock_file(&get_cache_file_path()?)?;
+    let mut store = load_cache_store();
+    apply_access_times(&mut store);
+
+    let (result, changed) = update(&mut store);
+    if changed {
+        cache_lock.write_json(&store)
+            .map_err(|e| format!("Failed to write cache: {}", e))?;
+
+        // Times for entries that are gone no longer matter
+        if let Some(access_times) = ACCESS_TIMES.lock().unwrap().as_mut() {
+            access_times.retain(|key, _| store.entries.contains_key(key));
+        }
+    }
+
+    Ok(result)
+}
+
+fn entry_ttl_seconds(entry: &WeatherCache, cache_duration_minutes: u64) -> i64 {
+    // Entries written before per-entry TTLs existed fall back to the current setting
+    if entry.ttl_seconds > 0 {
+        entry.ttl_seconds
+    } else {
+        (cache_duration_minutes * 60) as i64
+    }
+}
+
+fn is_entry_valid(entry: &WeatherCache, cache_duration_minutes: u64, now: i64) -> bool {
+    now - entry.cached_at < entry_ttl_seconds(entry, cache_duration_minutes)
+}
+
+fn evict_least_recently_used(store: &mut WeatherCacheStore) {
+    while store.entries.len() > MAX_CACHE_ENTRIES {
+        let oldest_key = store
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_accessed)
+            .map(|(key, _)| key.clone());
+
+        match oldest_key {
+            Some(key) => {
+                store.entries.remove(&key);
+            }
+            None => break,
+        }
+    }
+}
+
+/// Returns the cached response for a location if it is still within its TTL, noting the
+/// access so the entry survives LRU eviction. Never writes to disk.
+pub fn get_cached_weather(location_key: &str, cache_duration_minutes: u64) -> Option<WeatherApiResponse> {
+    let now = Utc::now().timestamp();
+
+    let data = load_cache_store()
+        .entries
+        .remove(location_key)
+        .filter(|entry| is_entry_valid(entry, cache_duration_minutes, now))
+        .map(|entry| entry.data)?;
+    record_access(location_key, now);
+    Some(data)
+}
+
+/// Returns the cached response for a location regardless of its age. Used as a
+/// fallback source (e.g. the forecast) when fresh data cannot be fetched.
+pub fn get_cached_weather_any_age(location_key: &str) -> Option<WeatherApiResponse> {
+    let store = load_cache_store();
+    store.entries.get(location_key).map(|entry| entry.data.clone())
+}
+
+pub fn store_cached_weather(location_key: &str, data: &WeatherApiResponse, cache_duration_minutes: u64) -> Result<(), String> {
+    let now = Utc::now().timestamp();
+
+    update_cache_store(|store| {
+        store.entries.insert(
+            location_key.to_string(),
+            WeatherCache {
+                data: data.clone(),
+                cached_at: now,
+                location_key: location_key.to_string(),
+                last_accessed: now,
+                ttl_seconds: (cache_duration_minutes * 60) as i64,
+            },
+        );
+        evict_least_recently_used(store);
+        ((), true)
+    })
+}
+
+pub fn list_cache_entries(cache_duration_minutes: u64) -> Vec<WeatherCacheEntryInfo> {
+    let mut store = load_cache_store();
+    apply_access_times(&mut store);
+    let now = Utc::now().timestamp();
+
+    let mut entries: Vec<WeatherCacheEntryInfo> = store
+        .entries
+        .values()
+        .map(|entry| WeatherCacheEntryInfo {
+            location_key: entry.location_key.clone(),
+            location: format!("{}, {}", entry.data.location.name, entry.data.location.country),
+            cached_at: entry.cached_at,
+            last_accessed: entry.last_accessed,
+            age_seconds: now - entry.cached_at,
+            ttl_seconds: entry_ttl_seconds(entry, cache_duration_minutes),
+            is_expired: !is_entry_valid(entry, cache_duration_minutes, now),
+        })
+        .collect();
+
+    // Most recently used first
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_accessed));
+    entries
+}
+
+/// Removes a single location from the cache. Returns whether an entry was present.
+pub fn remove_cache_entry(location_key: &str) -> Result<bool, String> {
+    update_cache_store(|store| {
+        let removed = store.entries.remove(location_key).is_some();
+        (removed, removed)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::utils::use_test_data_dir;
+
+    fn response(name: &str) -> WeatherApiResponse {
+        serde_json::from_value(serde_json::json!({
+            "location": { "name": name, "region": "", "country": "Nowhere" },
+            "current": { "temp_c": 12.0, "humidity": 80, "condition": { "text": "Light rain", "code": 1183 } },
+            "astronomy": { "astro": { "sunrise": "06:30 AM", "sunset": "07:45 PM" } },
+        }))
+        .unwrap()
+    }
+
+    fn stored_last_accessed(location_key: &str) -> i64 {
+        let content = fs::read_to_string(get_cache_file_path().unwrap()).unwrap();
+        let store: WeatherCacheStore = serde_json::from_str(&content).unwrap();
+        store.entries[location_key].last_accessed
+    }
+
+    #[test]
+    fn cache_hits_only_reach_disk_with_the_next_write() {
+        let _test_dir = use_test_data_dir();
+        store_cached_weather("rain", &response("Rain"), 60).unwrap();
+
+        // Pretend the entry was last used long ago
+        update_cache_store(|store| {
+            store.entries.get_mut("rain").unwrap().last_accessed = 1;
+            ((), true)
+        })
+        .unwrap();
+        *ACCESS_TIMES.lock().unwrap() = None;
+        let before = fs::read(get_cache_file_path().unwrap()).unwrap();
+
+        assert_eq!(get_cached_weather("rain", 60).unwrap().location.name, "Rain");
+        assert_eq!(fs::read(get_cache_file_path().unwrap()).unwrap(), before);
+        assert!(list_cache_entries(60)[0].last_accessed > 1);
+
+        store_cached_weather("snow", &response("Snow"), 60).unwrap();
+        assert!(stored_last_accessed("rain") > 1);
+    }
+
+    #[test]
+    fn expired_entries_are_not_served() {
+        let _test_dir = use_test_data_dir();
+        store_cached_weather("rain", &response("Rain"), 0).unwrap();
+        assert!(get_cached_weather("rain", 0).is_none());
+        assert!(get_cached_weather("missing", 60).is_none());
+    }
+}
@@ -1,10 +1,15 @@
+import { useEffect } from "react";
 import { useNavigationStore } from "./store/navigationStore";
 import { useSchedulerInitialization } from "./hooks/useSchedulerInitialization";
 import { useSchedulerSync } from "./hooks/useSchedulerSync";
+import { useConfigErrors } from "./hooks/useConfigErrors";
+import { useDeclarativeConfig } from "./hooks/useDeclarativeConfig";
+import { useCollectionStore } from "./store/collectionStore";
 import { HomePage } from "./pages/HomePage";
 import { CollectionsPage } from "./pages/CollectionsPage";
 import { SettingsPage } from "./pages/SettingsPage";
 import { IconSprite } from "./components/IconSprite";
+import { Alert } from "./components/ui/Alert";
 import "./App.css";
 
 function App() {
@@ -12,6 +17,16 @@ function App() {
 
   useSchedulerInitialization();
   useSchedulerSync();
+  const { errors: configErrors, dismiss: dismissConfigErrors } = useConfigErrors();
+  const { config: declarativeConfig } = useDeclarativeConfig();
+  const { applyDeclaredCollections } = useCollectionStore();
+
+  // Collections declared in config.toml replace the stored ones with the same id
+  useEffect(() => {
+    if (declarativeConfig) {
+      applyDeclaredCollections(declarativeConfig.collections, declarativeConfig.active_collection);
+    }
+  }, [declarativeConfig, applyDeclaredCollections]);
 
   const renderCurrentPage = () => {
     switch (currentPage) {
@@ -29,7 +44,19 @@ function App() {
   return (
     <main className="min-h-screen overflow-y-auto bg-bg-gradient relative">
       <IconSprite />
-      <div className="max-w-md mx-auto min-h-screen relative z-10">{renderCurrentPage()}</div>
+      <div className="max-w-md mx-auto min-h-screen relative z-10">
+        {configErrors.length > 0 && (
+          <Alert variant="warning" dismissible onDismiss={dismissConfigErrors} className="m-4">
+            {configErrors.map(error => (
+              <p key={`${error.file}-${error.occurred_at}`}>
+                {error.file}: {error.message}
+                {error.backup_path && ` The original was saved to ${error.backup_path}.`}
+              </p>
+            ))}
+          </Alert>
+        )}
+        {renderCurrentPage()}
+      </div>
     </main>
   );
 }
@@ -283,6 +283,10 @@ export function CollectionSelector({ onCollectionChange }: CollectionSelectorPro
                         </svg>
                       </button>
                     </>
+                  ) : collection.declared ? (
+                    <span className="text-xs text-text-secondary" title="Defined in config.toml">
+                      config.toml
+                    </span>
                   ) : (
                     <>
                       <button
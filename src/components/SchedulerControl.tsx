@@ -1,18 +1,25 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { Icon } from "./ui/Icon";
+import { useDeclarativeConfig } from "../hooks/useDeclarativeConfig";
 
 interface SchedulerStatus {
   enabled: boolean;
   interval_minutes: number;
   is_running: boolean;
   last_applied_path: string | null;
+  external_change_action: "pause" | "reapply";
+  manual_override: { detected_at: string; wallpaper: string | null } | null;
 }
 
 export function SchedulerControl() {
   const [status, setStatus] = useState<SchedulerStatus | null>(null);
   const [isLoading, setIsLoading] = useState(false);
   const [intervalMinutes, setIntervalMinutes] = useState(3);
+  const { config: declarativeConfig } = useDeclarativeConfig();
+  const enabledDeclared = declarativeConfig?.scheduler.enabled != null;
+  const intervalDeclared = declarativeConfig?.scheduler.interval_minutes != null;
 
   const fetchStatus = async () => {
     try {
@@ -48,12 +55,39 @@ export function SchedulerControl() {
     }
   };
 
+  const resumeAutomation = async () => {
+    setIsLoading(true);
+    try {
+      await invoke("resume_wallpaper_automation");
+      await fetchStatus();
+    } catch (error) {
+      console.error("Failed to resume automation:", error);
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  const updateExternalChangeAction = async (action: SchedulerStatus["external_change_action"]) => {
+    try {
+      await invoke("set_external_change_action", { action });
+      await fetchStatus();
+    } catch (error) {
+      console.error("Failed to update external change action:", error);
+    }
+  };
+
   useEffect(() => {
     fetchStatus();
 
-    // Refresh status every 30 seconds
+    // Refresh status every 30 seconds, and right away when the config file is edited
     const interval = setInterval(fetchStatus, 30000);
-    return () => clearInterval(interval);
+    const unlisten = listen("scheduler-config-changed", () => fetchStatus());
+    const unlistenOverride = listen("manual-override-detected", () => fetchStatus());
+    return () => {
+      clearInterval(interval);
+      unlisten.then(fn => fn());
+      unlistenOverride.then(fn => fn());
+    };
   }, []);
 
   if (!status) {
@@ -75,7 +109,11 @@ export function SchedulerControl() {
               : "bg-danger-light text-danger-hover"
           }`}
         >
-          {status.enabled && status.is_running ? "ACTIVE" : "INACTIVE"}
+          {status.manual_override
+            ? "PAUSED"
+            : status.enabled && status.is_running
+              ? "ACTIVE"
+              : "INACTIVE"}
         </div>
       </div>
 
@@ -84,6 +122,25 @@ export function SchedulerControl() {
       </p>
 
       <div className="space-y-4">
+        {status.manual_override && (
+          <div className="p-3 rounded-lg bg-warning-light/30 border border-warning/20 space-y-2">
+            <p className="text-xs text-text-secondary leading-relaxed">
+              <strong className="text-warning">Manual override detected:</strong> the wallpaper
+              was changed outside the app
+              {status.manual_override.wallpaper &&
+                ` (to ${status.manual_override.wallpaper.split(/[\\/]/).pop()})`}
+              , so automatic changes are paused.
+            </p>
+            <button
+              onClick={resumeAutomation}
+              disabled={isLoading}
+              className="w-full bg-primary hover:bg-primary-hover disabled:bg-border text-text-inverse font-medium py-2 px-4 rounded-lg text-sm transition-colors disabled:cursor-not-allowed cursor-pointer"
+            >
+              Resume Auto Wallpaper
+            </button>
+          </div>
+        )}
+
         {/* Interval Control */}
         <div>
           <label className="block text-sm font-medium text-text-primary mb-2">
@@ -95,7 +152,7 @@ export function SchedulerControl() {
             max="30"
             value={intervalMinutes}
             onChange={e => setIntervalMinutes(parseInt(e.target.value))}
-            disabled={status.enabled && status.is_running}
+            disabled={(status.enabled && status.is_running) || intervalDeclared}
             className="w-full h-2 bg-surface rounded-full appearance-none cursor-pointer slider disabled:cursor-not-allowed"
           />
           <div className="flex justify-between text-xs text-text-secondary mt-1">
@@ -110,7 +167,7 @@ export function SchedulerControl() {
           {status.enabled && status.is_running ? (
             <button
               onClick={stopScheduler}
-              disabled={isLoading}
+              disabled={isLoading || enabledDeclared}
               className="flex-1 bg-danger hover:bg-danger-hover disabled:bg-border text-text-inverse font-medium py-2 px-4 rounded-lg text-sm transition-colors disabled:cursor-not-allowed cursor-pointer"
             >
               {isLoading ? "Stopping..." : "Stop Auto Wallpaper"}
@@ -118,7 +175,7 @@ export function SchedulerControl() {
           ) : (
             <button
               onClick={startScheduler}
-              disabled={isLoading}
+              disabled={isLoading || enabledDeclared}
               className="flex-1 bg-primary hover:bg-primary-hover disabled:bg-border text-text-inverse font-medium py-2 px-4 rounded-lg text-sm transition-colors disabled:cursor-not-allowed cursor-pointer"
             >
               {isLoading ? "Starting..." : "Start Auto Wallpaper"}
@@ -135,6 +192,28 @@ export function SchedulerControl() {
           </button>
         </div>
 
+        <div>
+          <label className="block text-sm font-medium text-text-primary mb-2">
+            When the wallpaper is changed elsewhere
+          </label>
+          <select
+            value={status.external_change_action}
+            onChange={e =>
+              updateExternalChangeAction(e.target.value as SchedulerStatus["external_change_action"])
+            }
+            className="w-full px-3 py-2 border border-border rounded-lg bg-card text-text-primary text-sm"
+          >
+            <option value="pause">Pause automatic changes</option>
+            <option value="reapply">Switch back to the scheduled wallpaper</option>
+          </select>
+        </div>
+
+        {(enabledDeclared || intervalDeclared) && (
+          <p className="text-xs text-text-secondary">
+            Managed in {declarativeConfig?.path}; edit that file to change the schedule.
+          </p>
+        )}
+
         {/* Status Info */}
         {status.last_applied_path && (
           <div className="text-xs text-text-secondary bg-surface p-2 rounded">
@@ -18,6 +18,7 @@ interface WallpaperCardProps {
   allSettings: WallpaperSettings;
   onToggleCategory: (category: WallpaperCategory) => void;
   onUpdatePriority: (category: WallpaperCategory, priority: number) => void;
+  onToggleLockScreen: (category: WallpaperCategory) => void;
   onFileSelect: (category: WallpaperCategory) => void;
 }
 
@@ -52,6 +53,7 @@ export function WallpaperCard({
   allSettings,
   onToggleCategory,
   onUpdatePriority,
+  onToggleLockScreen,
   onFileSelect,
 }: WallpaperCardProps) {
   // const isCurrentlyActive =
@@ -167,6 +169,15 @@ export function WallpaperCard({
                     Change
                   </button>
                 </div>
+                <label className="flex items-center gap-2 text-xs text-text-secondary cursor-pointer">
+                  <input
+                    type="checkbox"
+                    checked={setting.lockScreen ?? false}
+                    onChange={() => onToggleLockScreen(categoryInfo.key)}
+                    className="accent-primary"
+                  />
+                  Also use on the lock screen
+                </label>
               </div>
             ) : (
               <button
@@ -0,0 +1,40 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+export interface ConfigLoadError {
+  file: string;
+  message: string;
+  backup_path: string | null;
+  occurred_at: string;
+}
+
+export function useConfigErrors() {
+  const [errors, setErrors] = useState<ConfigLoadError[]>([]);
+
+  useEffect(() => {
+    // Errors from loads that happened before the window was ready
+    invoke("get_config_errors")
+      .then(result => setErrors(result as ConfigLoadError[]))
+      .catch(error => console.error("Failed to fetch config errors:", error));
+
+    const unlisten = listen<ConfigLoadError>("config-load-error", event => {
+      setErrors(current => [...current, event.payload]);
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  const dismiss = async () => {
+    setErrors([]);
+    try {
+      await invoke("dismiss_config_errors");
+    } catch (error) {
+      console.error("Failed to dismiss config errors:", error);
+    }
+  };
+
+  return { errors, dismiss };
+}
@@ -0,0 +1,46 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { WallpaperSetting } from "../types";
+
+export interface DeclaredCollection {
+  id: string;
+  name: string;
+  settings: { [category: string]: Partial<WallpaperSetting> };
+}
+
+// Configuration declared in config.toml; declared fields are read-only in the UI
+export interface DeclarativeConfig {
+  path: string | null;
+  loaded: boolean;
+  error: string | null;
+  settings: { [field: string]: unknown };
+  scheduler: {
+    enabled: boolean | null;
+    interval_minutes: number | null;
+  };
+  active_collection: string | null;
+  collections: { [id: string]: DeclaredCollection };
+}
+
+export function useDeclarativeConfig() {
+  const [config, setConfig] = useState<DeclarativeConfig | null>(null);
+
+  useEffect(() => {
+    const fetchConfig = () =>
+      invoke("get_declarative_config")
+        .then(result => setConfig(result as DeclarativeConfig))
+        .catch(error => console.error("Failed to fetch declarative config:", error));
+
+    fetchConfig();
+    const unlisten = listen("declarative-config-changed", () => fetchConfig());
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  const isSettingDeclared = (field: string) => !!config && field in config.settings;
+
+  return { config, isSettingDeclared };
+}
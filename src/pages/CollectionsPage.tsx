@@ -85,6 +85,10 @@ export function CollectionsPage() {
     updateSetting(category, { priority });
   };
 
+  const toggleLockScreen = (category: WallpaperCategory) => {
+    updateSetting(category, { lockScreen: !settings[category].lockScreen });
+  };
+
   useEffect(() => {
     return () => {
       if (messageTimeoutRef.current) {
@@ -186,7 +190,17 @@ export function CollectionsPage() {
             />
           </div>
 
-          <div className="space-y-3 transition-all duration-300 ease-in-out pb-10">
+          {activeCollection.declared && (
+            <Alert variant="info">
+              This collection is defined in config.toml and can only be changed there.
+            </Alert>
+          )}
+
+          {/* Declared collections are read-only */}
+          <fieldset
+            disabled={activeCollection.declared}
+            className="space-y-3 transition-all duration-300 ease-in-out pb-10"
+          >
             {activeTab === "weather" &&
               weatherCategories.map(categoryInfo => {
                 const setting = settings[categoryInfo.key];
@@ -202,6 +216,7 @@ export function CollectionsPage() {
                     allSettings={settings}
                     onToggleCategory={toggleCategory}
                     onUpdatePriority={updatePriority}
+                    onToggleLockScreen={toggleLockScreen}
                     onFileSelect={handleFileSelect}
                   />
                 );
@@ -222,6 +237,7 @@ export function CollectionsPage() {
                     allSettings={settings}
                     onToggleCategory={toggleCategory}
                     onUpdatePriority={updatePriority}
+                    onToggleLockScreen={toggleLockScreen}
                     onFileSelect={handleFileSelect}
                   />
                 );
@@ -242,11 +258,12 @@ export function CollectionsPage() {
                     allSettings={settings}
                     onToggleCategory={toggleCategory}
                     onUpdatePriority={updatePriority}
+                    onToggleLockScreen={toggleLockScreen}
                     onFileSelect={handleFileSelect}
                   />
                 );
               })}
-          </div>
+          </fieldset>
         </div>
       ) : (
         <div className="text-center py-12 bg-white/5 rounded-xl border border-white/10">
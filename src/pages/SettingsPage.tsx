@@ -1,10 +1,12 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { save, open } from "@tauri-apps/plugin-dialog";
 import { enable, disable, isEnabled } from "@tauri-apps/plugin-autostart";
 import { SchedulerControl } from "../components/SchedulerControl";
 import { useNavigationStore } from "../store/navigationStore";
 import { useCollectionStore } from "../store/collectionStore";
+import { useDeclarativeConfig } from "../hooks/useDeclarativeConfig";
 import { Icon } from "../components/ui/Icon";
 import { Button } from "../components/ui/Button";
 
@@ -13,6 +15,64 @@ interface AppSettings {
   location: string;
   use_auto_location: boolean;
   cache_duration_minutes: number;
+  auto_backup_enabled?: boolean;
+  auto_backup_frequency?: "daily" | "weekly";
+  auto_backup_retention?: number;
+  wallpaper_backend?: string;
+  wallpaper_transition?: "none" | "crossfade" | "wipe" | "grow";
+  wallpaper_transition_duration_ms?: number;
+}
+
+interface WallpaperBackendStatus {
+  detected: string;
+  active: string;
+  backends: { id: string; name: string; available: boolean }[];
+}
+
+interface AutoBackupStatus {
+  last_success_at: string | null;
+  last_backup_path: string | null;
+  last_attempt_at: string | null;
+  last_error: string | null;
+  next_due_at: string | null;
+}
+
+interface BackupValidationReport {
+  version: string | null;
+  created_at: string | null;
+  is_compatible: boolean;
+  has_manifest: boolean;
+  has_encrypted_secrets: boolean;
+  missing_files: string[];
+  corrupt_files: string[];
+  extra_files: string[];
+  errors: string[];
+  is_valid: boolean;
+}
+
+interface ImportOptions {
+  settings: boolean;
+  scheduler_config: boolean;
+  collections: boolean;
+  wallpapers: boolean;
+  collection_ids: string[] | null;
+  mode: "replace" | "merge";
+  existing_collection_ids: string[];
+  passphrase: string | null;
+}
+
+interface ImportResult {
+  message: string;
+  collections_data: { collections?: Record<string, unknown>; activeCollectionId?: string | null } | null;
+  renamed_collections: Record<string, string>;
+}
+
+interface BackupProgress {
+  processed_files: number;
+  total_files: number;
+  processed_bytes: number;
+  total_bytes: number;
+  current_file: string | null;
 }
 
 export function SettingsPage() {
@@ -22,6 +82,17 @@ export function SettingsPage() {
   const [isLoading, setIsLoading] = useState(false);
   const [isImporting, setIsImporting] = useState(false);
   const [autostartEnabled, setAutostartEnabled] = useState(false);
+  const [autoBackupStatus, setAutoBackupStatus] = useState<AutoBackupStatus | null>(null);
+  const [backendStatus, setBackendStatus] = useState<WallpaperBackendStatus | null>(null);
+  const [includeSecrets, setIncludeSecrets] = useState(false);
+  const [backupPassphrase, setBackupPassphrase] = useState("");
+  const [importOptions, setImportOptions] = useState({
+    settings: true,
+    scheduler_config: true,
+    collections: true,
+    wallpapers: true,
+    mode: "replace" as ImportOptions["mode"],
+  });
   const [settings, setSettings] = useState<AppSettings>({
     weather_api_key: "",
     location: "",
@@ -29,6 +100,8 @@ export function SettingsPage() {
     cache_duration_minutes: 60,
   });
 
+  const { config: declarativeConfig, isSettingDeclared } = useDeclarativeConfig();
+
   const setMessageWithAutoDismiss = (msg: string, isError = false) => {
     setMessage(msg);
     setTimeout(() => setMessage(""), isError ? 5000 : 3000);
@@ -44,6 +117,35 @@ export function SettingsPage() {
     }
   };
 
+  const fetchAutoBackupStatus = async () => {
+    try {
+      setAutoBackupStatus((await invoke("get_auto_backup_status")) as AutoBackupStatus);
+    } catch (error) {
+      console.error("Failed to fetch automatic backup status:", error);
+    }
+  };
+
+  const fetchBackendStatus = async () => {
+    try {
+      setBackendStatus((await invoke("get_wallpaper_backends")) as WallpaperBackendStatus);
+    } catch (error) {
+      console.error("Failed to fetch wallpaper backends:", error);
+    }
+  };
+
+  const handleTransitionChange = async (updates: Partial<AppSettings>) => {
+    const newSettings = { ...settings, ...updates };
+    setSettings(newSettings);
+    await saveSettings(false, newSettings);
+  };
+
+  const handleWallpaperBackendChange = async (backend: string) => {
+    const newSettings = { ...settings, wallpaper_backend: backend };
+    setSettings(newSettings);
+    await saveSettings(false, newSettings);
+    await fetchBackendStatus();
+  };
+
   const checkAutostart = async () => {
     try {
       const enabled = await isEnabled();
@@ -118,6 +220,23 @@ export function SettingsPage() {
     await saveSettings(false, newSettings);
   };
 
+  const handleAutoBackupChange = async (updates: Partial<AppSettings>) => {
+    const newSettings = { ...settings, ...updates };
+    setSettings(newSettings);
+    await saveSettings(false, newSettings);
+    await fetchAutoBackupStatus();
+  };
+
+  const runAutoBackupNow = async () => {
+    try {
+      setAutoBackupStatus((await invoke("run_auto_backup_now")) as AutoBackupStatus);
+      setMessageWithAutoDismiss("Backup created");
+    } catch (error) {
+      setMessageWithAutoDismiss(`Backup failed: ${error}`, true);
+      await fetchAutoBackupStatus();
+    }
+  };
+
   const clearWeatherCache = async () => {
     try {
       const result = (await invoke("clear_weather_cache")) as string;
@@ -128,6 +247,11 @@ export function SettingsPage() {
   };
 
   const exportBackup = async () => {
+    if (includeSecrets && !backupPassphrase) {
+      setMessageWithAutoDismiss("Enter a passphrase to include the API key in the backup", true);
+      return;
+    }
+
     try {
       const timestamp = new Date().toISOString().split("T")[0];
       const defaultFilename = `wallpaperthing-backup-${timestamp}.zip`;
@@ -152,17 +276,23 @@ export function SettingsPage() {
         activeCollectionId: collectionStore.activeCollectionId,
       });
 
-      const zipData = (await invoke("export_backup", {
-        collectionsData,
-      })) as number[];
-      const uint8Array = new Uint8Array(zipData);
-
-      // Write the ZIP file using Tauri's file system
-      await invoke("write_backup_file", {
-        path: savePath,
-        data: Array.from(uint8Array),
+      // Stream the backup straight to disk, reporting progress as wallpapers are written
+      const unlisten = await listen<BackupProgress>("backup-export-progress", event => {
+        const { processed_files, total_files } = event.payload;
+        setMessage(`Exporting wallpapers... ${processed_files}/${total_files}`);
       });
 
+      try {
+        await invoke("export_backup_to_file", {
+          path: savePath,
+          collectionsData,
+          includeSecrets,
+          passphrase: includeSecrets ? backupPassphrase : null,
+        });
+      } finally {
+        unlisten();
+      }
+
       setMessageWithAutoDismiss("Backup exported successfully!");
     } catch (error) {
       setMessageWithAutoDismiss(`Export failed: ${error}`, true);
@@ -192,35 +322,72 @@ export function SettingsPage() {
       const fileData = (await invoke("read_backup_file", { path: filePath })) as number[];
       const zipData = new Uint8Array(fileData);
 
-      setMessageWithAutoDismiss("Restoring settings and wallpapers...");
-
-      // First restore the backend data (settings, scheduler, wallpapers)
-      const result = (await invoke("import_backup", { zipData: Array.from(zipData) })) as string;
-
-      // Then restore collections data if present
-      const collectionsDataStr = (await invoke("get_backup_collections_data", {
+      // Verify checksums before touching anything and let the user decide on damaged backups
+      const report = (await invoke("validate_backup", {
         zipData: Array.from(zipData),
-      })) as string | null;
+      })) as BackupValidationReport;
+
+      let force = false;
+      if (!report.is_valid) {
+        const problems = [
+          ...report.errors,
+          ...report.missing_files.map(file => `Missing: ${file}`),
+          ...report.corrupt_files.map(file => `Corrupt: ${file}`),
+        ];
+        if (!report.is_compatible || !window.confirm(
+          `This backup failed integrity checks:\n${problems.join("\n")}\n\nImport anyway?`
+        )) {
+          setMessageWithAutoDismiss(`Import cancelled: ${problems.join(", ")}`, true);
+          return;
+        }
+        force = true;
+      }
+
+      // Encrypted secrets are only needed when settings are being restored
+      let passphrase: string | null = null;
+      if (report.has_encrypted_secrets && importOptions.settings) {
+        passphrase = window.prompt("This backup's API key is encrypted. Enter the backup passphrase:");
+        if (passphrase === null) {
+          setMessageWithAutoDismiss("Import cancelled", true);
+          return;
+        }
+      }
 
-      if (collectionsDataStr) {
-        try {
-          const collectionsData = JSON.parse(collectionsDataStr);
+      setMessageWithAutoDismiss("Restoring settings and wallpapers...");
 
+      // Restore the selected backend data (settings, scheduler, wallpapers); collections
+      // come back already remapped for this machine and are applied to the store below
+      const options: ImportOptions = {
+        ...importOptions,
+        collection_ids: null,
+        existing_collection_ids: Object.keys(collectionStore.collections),
+        passphrase,
+      };
+      const result = (await invoke("import_backup_selective", {
+        zipData: Array.from(zipData),
+        force,
+        options,
+      })) as ImportResult;
+
+      if (result.collections_data) {
+        const restored = result.collections_data.collections || {};
+
+        if (options.mode === "merge") {
+          // Add restored collections alongside the existing ones
+          useCollectionStore.setState(state => ({
+            collections: { ...state.collections, ...restored } as typeof state.collections,
+          }));
+        } else {
           // Restore the entire collections state
           useCollectionStore.setState({
-            collections: collectionsData.collections || {},
-            activeCollectionId: collectionsData.activeCollectionId || null,
+            collections: restored as typeof collectionStore.collections,
+            activeCollectionId: result.collections_data.activeCollectionId || null,
           });
-
-          setMessageWithAutoDismiss(result + "\nCollections restored successfully!");
-        } catch (error) {
-          setMessageWithAutoDismiss(
-            result + "\nWarning: Failed to restore collections data.",
-            true
-          );
         }
+
+        setMessageWithAutoDismiss(result.message + "\nCollections restored successfully!");
       } else {
-        setMessageWithAutoDismiss(result);
+        setMessageWithAutoDismiss(result.message);
       }
 
       // Comprehensive refresh of all app data
@@ -273,6 +440,18 @@ export function SettingsPage() {
   useEffect(() => {
     fetchSettings();
     checkAutostart();
+    fetchAutoBackupStatus();
+    fetchBackendStatus();
+
+    // Pick up edits made to the settings file outside the app
+    const unlisten = listen("settings-changed", () => {
+      fetchSettings();
+      fetchAutoBackupStatus();
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
   }, []);
 
   return (
@@ -314,6 +493,15 @@ export function SettingsPage() {
         <h1 className="text-xl font-bold text-text-primary">Settings</h1>
       </div>
 
+      {declarativeConfig?.loaded && Object.keys(declarativeConfig.settings).length > 0 && (
+        <div className="p-3 bg-surface/50 border border-border/50 rounded-xl">
+          <p className="text-xs text-text-secondary leading-relaxed">
+            Some settings are managed in {declarativeConfig.path} and can only be changed there:{" "}
+            {Object.keys(declarativeConfig.settings).join(", ")}
+          </p>
+        </div>
+      )}
+
       {/* Status Message */}
       {message && (
         <div
@@ -390,6 +578,7 @@ export function SettingsPage() {
               type="checkbox"
               checked={settings.use_auto_location}
               onChange={e => handleAutoLocationChange(e.target.checked)}
+              disabled={isSettingDeclared("use_auto_location")}
               className="w-4 h-4 text-white bg-card border border-border rounded-md focus:ring-primary focus:ring-2 accent-primary"
             />
             <label
@@ -409,6 +598,7 @@ export function SettingsPage() {
                 type="text"
                 value={settings.location}
                 onChange={e => setSettings({ ...settings, location: e.target.value })}
+                disabled={isSettingDeclared("location")}
                 onBlur={handleLocationBlur}
                 placeholder="e.g., New York, London, 40.7128,-74.0060"
                 className="w-full px-3 py-2 border border-border rounded-xl bg-card text-text-primary text-sm focus:ring-2 focus:ring-primary focus:border-transparent transition-all duration-200"
@@ -440,6 +630,73 @@ export function SettingsPage() {
               Run on Startup
             </label>
           </div>
+          <div>
+            <label className="block text-sm font-medium text-text-primary mb-2">
+              Wallpaper Backend
+            </label>
+            <select
+              value={settings.wallpaper_backend ?? "auto"}
+              onChange={e => handleWallpaperBackendChange(e.target.value)}
+              disabled={isSettingDeclared("wallpaper_backend")}
+              className="w-full px-3 py-2 border border-border rounded-xl bg-card text-text-primary text-sm focus:ring-2 focus:ring-primary focus:border-transparent transition-all duration-200"
+            >
+              <option value="auto">
+                Automatic
+                {backendStatus &&
+                  ` (${backendStatus.backends.find(b => b.id === backendStatus.detected)?.name ?? backendStatus.detected})`}
+              </option>
+              {backendStatus?.backends.map(backend => (
+                <option key={backend.id} value={backend.id}>
+                  {backend.name}
+                  {!backend.available && " (not detected)"}
+                </option>
+              ))}
+            </select>
+            <p className="text-xs text-text-secondary mt-2 leading-relaxed">
+              How wallpapers are applied. Pick one manually if automatic detection doesn't work
+              with your desktop.
+            </p>
+          </div>
+          <div>
+            <label className="block text-sm font-medium text-text-primary mb-2">Transition</label>
+            <div className="grid grid-cols-2 gap-2">
+              <select
+                value={settings.wallpaper_transition ?? "none"}
+                onChange={e =>
+                  handleTransitionChange({
+                    wallpaper_transition: e.target.value as AppSettings["wallpaper_transition"],
+                  })
+                }
+                disabled={isSettingDeclared("wallpaper_transition")}
+                className="w-full px-3 py-2 border border-border rounded-xl bg-card text-text-primary text-sm focus:ring-2 focus:ring-primary focus:border-transparent transition-all duration-200"
+              >
+                <option value="none">None</option>
+                <option value="crossfade">Crossfade</option>
+                <option value="wipe">Wipe</option>
+                <option value="grow">Grow</option>
+              </select>
+              <select
+                value={settings.wallpaper_transition_duration_ms ?? 1000}
+                onChange={e =>
+                  handleTransitionChange({ wallpaper_transition_duration_ms: parseInt(e.target.value) })
+                }
+                disabled={
+                  (settings.wallpaper_transition ?? "none") === "none" ||
+                  isSettingDeclared("wallpaper_transition_duration_ms")
+                }
+                className="w-full px-3 py-2 border border-border rounded-xl bg-card text-text-primary text-sm focus:ring-2 focus:ring-primary focus:border-transparent transition-all duration-200"
+              >
+                {[500, 1000, 2000, 3000].map(duration => (
+                  <option key={duration} value={duration}>
+                    {duration / 1000} s
+                  </option>
+                ))}
+              </select>
+            </div>
+            <p className="text-xs text-text-secondary mt-2 leading-relaxed">
+              swww animates natively; other backends show a few blended frames in between.
+            </p>
+          </div>
         </div>
       </div>
 
@@ -458,6 +715,7 @@ export function SettingsPage() {
             <select
               value={settings.cache_duration_minutes}
               onChange={e => handleCacheDurationChange(parseInt(e.target.value))}
+              disabled={isSettingDeclared("cache_duration_minutes")}
               className="w-full px-3 py-2 border border-border rounded-xl bg-card text-text-primary text-sm focus:ring-2 focus:ring-primary focus:border-transparent transition-all duration-200"
             >
               <option value={5}>5 minutes</option>
@@ -519,12 +777,119 @@ export function SettingsPage() {
                 )}
               </button>
             </div>
+            <div className="mt-4 space-y-2">
+              <p className="text-sm font-medium text-text-primary">Export</p>
+              <label className="flex items-center space-x-2 text-sm text-text-secondary">
+                <input
+                  type="checkbox"
+                  checked={includeSecrets}
+                  disabled={isImporting}
+                  onChange={e => setIncludeSecrets(e.target.checked)}
+                />
+                <span>Include weather API key</span>
+              </label>
+              {includeSecrets && (
+                <input
+                  type="password"
+                  value={backupPassphrase}
+                  disabled={isImporting}
+                  onChange={e => setBackupPassphrase(e.target.value)}
+                  placeholder="Passphrase to encrypt the API key (required)"
+                  className="w-full px-3 py-2 border border-border rounded-xl bg-card text-text-primary text-sm focus:ring-2 focus:ring-primary focus:border-transparent transition-all duration-200"
+                />
+              )}
+            </div>
+            <div className="mt-4 space-y-2">
+              <p className="text-sm font-medium text-text-primary">Restore</p>
+              <div className="grid grid-cols-2 gap-2">
+                {(
+                  [
+                    ["settings", "Settings"],
+                    ["scheduler_config", "Scheduler"],
+                    ["collections", "Collections"],
+                    ["wallpapers", "Images"],
+                  ] as const
+                ).map(([key, label]) => (
+                  <label key={key} className="flex items-center space-x-2 text-sm text-text-secondary">
+                    <input
+                      type="checkbox"
+                      checked={importOptions[key]}
+                      disabled={isImporting}
+                      onChange={e => setImportOptions({ ...importOptions, [key]: e.target.checked })}
+                    />
+                    <span>{label}</span>
+                  </label>
+                ))}
+              </div>
+              <select
+                value={importOptions.mode}
+                disabled={isImporting}
+                onChange={e =>
+                  setImportOptions({ ...importOptions, mode: e.target.value as ImportOptions["mode"] })
+                }
+                className="w-full px-3 py-2 border border-border rounded-xl bg-card text-text-primary text-sm focus:ring-2 focus:ring-primary focus:border-transparent transition-all duration-200"
+              >
+                <option value="replace">Replace existing collections</option>
+                <option value="merge">Merge with existing collections</option>
+              </select>
+            </div>
+            <div className="mt-4 space-y-2">
+              <p className="text-sm font-medium text-text-primary">Automatic backups</p>
+              <label className="flex items-center space-x-2 text-sm text-text-secondary">
+                <input
+                  type="checkbox"
+                  checked={settings.auto_backup_enabled ?? false}
+                  onChange={e => handleAutoBackupChange({ auto_backup_enabled: e.target.checked })}
+                  disabled={isSettingDeclared("auto_backup_enabled")}
+                />
+                <span>Back up automatically</span>
+              </label>
+              <div className="grid grid-cols-2 gap-2">
+                <select
+                  value={settings.auto_backup_frequency ?? "daily"}
+                  onChange={e =>
+                    handleAutoBackupChange({
+                      auto_backup_frequency: e.target.value as AppSettings["auto_backup_frequency"],
+                    })
+                  }
+                  disabled={isSettingDeclared("auto_backup_frequency")}
+                  className="w-full px-3 py-2 border border-border rounded-xl bg-card text-text-primary text-sm focus:ring-2 focus:ring-primary focus:border-transparent transition-all duration-200"
+                >
+                  <option value="daily">Daily</option>
+                  <option value="weekly">Weekly</option>
+                </select>
+                <select
+                  value={settings.auto_backup_retention ?? 7}
+                  onChange={e => handleAutoBackupChange({ auto_backup_retention: parseInt(e.target.value) })}
+                  disabled={isSettingDeclared("auto_backup_retention")}
+                  className="w-full px-3 py-2 border border-border rounded-xl bg-card text-text-primary text-sm focus:ring-2 focus:ring-primary focus:border-transparent transition-all duration-200"
+                >
+                  {[3, 7, 14, 30].map(count => (
+                    <option key={count} value={count}>
+                      Keep last {count}
+                    </option>
+                  ))}
+                </select>
+              </div>
+              <div className="flex items-center justify-between">
+                <p className="text-xs text-text-secondary leading-relaxed">
+                  {autoBackupStatus?.last_success_at
+                    ? `Last backup: ${new Date(autoBackupStatus.last_success_at).toLocaleString()}`
+                    : "No automatic backup yet"}
+                  {autoBackupStatus?.last_error && ` (last attempt failed: ${autoBackupStatus.last_error})`}
+                </p>
+                <Button onClick={runAutoBackupNow} variant="secondary" className="cursor-pointer">
+                  Back up now
+                </Button>
+              </div>
+            </div>
             <div className="mt-4 p-3 bg-warning-light/30 border border-warning/20 rounded-xl">
               <div className="flex items-start space-x-2">
                 <Icon name="settings" size={16} className="text-warning mt-0.5 flex-shrink-0" />
                 <p className="text-xs text-text-secondary leading-relaxed">
-                  <strong className="text-warning">Important:</strong> Importing will replace your
-                  current settings and collections. Make sure to export a backup first!
+                  <strong className="text-warning">Important:</strong> Importing will replace the
+                  selected items. Merging keeps your collections and renames restored ones whose
+                  IDs clash. Make sure to export a backup first!
                 </p>
               </div>
             </div>
@@ -2,6 +2,7 @@ import { create } from "zustand";
 import { persist } from "zustand/middleware";
 import { WallpaperCollection, CollectionStore, WALLPAPER_CATEGORIES } from "../types";
 import { invoke } from "@tauri-apps/api/core";
+import { DeclaredCollection } from "../hooks/useDeclarativeConfig";
 
 interface CollectionStoreActions {
   createCollection: (name: string) => string;
@@ -10,6 +11,10 @@ interface CollectionStoreActions {
   setActiveCollection: (id: string) => Promise<void>;
   getActiveCollection: () => WallpaperCollection | null;
   validateCollection: (collection: WallpaperCollection) => { isValid: boolean; error?: string };
+  applyDeclaredCollections: (
+    declared: { [id: string]: DeclaredCollection },
+    activeCollectionId: string | null
+  ) => void;
 }
 
 const createDefaultCollection = (name: string, id: string): WallpaperCollection => {
@@ -127,6 +132,38 @@ export const useCollectionStore = create<CollectionStore & CollectionStoreAction
 
         return { isValid: true };
       },
+
+      applyDeclaredCollections: (declared, activeCollectionId) => {
+        set(state => {
+          // Collections no longer in config.toml stay, but become editable again
+          const collections = Object.fromEntries(
+            Object.entries(state.collections).map(([id, collection]) => [
+              id,
+              collection.declared ? { ...collection, declared: false } : collection,
+            ])
+          );
+
+          for (const [id, collection] of Object.entries(declared)) {
+            const base = createDefaultCollection(collection.name, id);
+            const settings = { ...base.settings };
+            for (const [category, setting] of Object.entries(collection.settings)) {
+              settings[category] = { ...settings[category], ...setting };
+            }
+
+            collections[id] = {
+              ...base,
+              settings,
+              createdAt: state.collections[id]?.createdAt ?? base.createdAt,
+              declared: true,
+            };
+          }
+
+          return {
+            collections,
+            activeCollectionId: activeCollectionId ?? state.activeCollectionId,
+          };
+        });
+      },
     }),
     {
       name: "wallpaper-collections",
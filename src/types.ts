@@ -20,6 +20,7 @@ export interface WallpaperSetting {
   imagePath: string | null;
   priority: number; // Higher number = higher priority
   enabled: boolean;
+  lockScreen?: boolean; // Also set the image as the lock screen
 }
 
 export interface WallpaperSettings {
@@ -32,6 +33,7 @@ export interface WallpaperCollection {
   settings: WallpaperSettings;
   createdAt: string;
   lastModified: string;
+  declared?: boolean; // Defined in config.toml and read-only in the UI
 }
 
 export interface CollectionStore {